@@ -0,0 +1,30 @@
+//! Generates the static prayer time indexes served to the apps, out of the monthly timetables
+//!
+//! The `ptig` binary is a thin command line over this library. Library users can load a year
+//! with [`v1::generator::Generator::new`], or build one out of days loaded elsewhere with
+//! [`v1::generator::Generator::from_days`], and render every index in memory with its `render_*`
+//! methods without touching the filesystem, or write them to any [`sink::OutputSink`]:
+//!
+//! ```no_run
+//! use prayer_time_index_generator::v1::generator::Generator;
+//! use prayer_time_index_generator::v1::domain::WeekDay;
+//! use prayer_time_index_generator::v1::domain::WeekScheme;
+//!
+//! # fn main() -> prayer_time_index_generator::prelude::Fallible<()> {
+//! let mut generator = Generator::new(2025, "input/2025".into(), true)?;
+//! generator.make_weeks(WeekDay::Sat, WeekScheme::Chunked)?;
+//! let year = generator.render_year_idx()?;
+//! let weeks = generator.render_week_idx()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The command line itself, along with the project file, watch mode and preview server, is behind
+//! the default `cli` feature, which library users can turn off to leave out its dependencies.
+
+#[macro_use]
+mod macros;
+pub mod prelude;
+pub mod sink;
+pub mod staging;
+pub mod v1;
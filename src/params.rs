@@ -1,5 +1,5 @@
-use crate::v1::params::V1Params;
 use clap::Parser;
+use prayer_time_index_generator::v1::params::V1Params;
 
 #[derive(Debug, Parser)]
 pub enum CliParams {
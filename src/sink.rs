@@ -0,0 +1,253 @@
+use crate::prelude::Fallible;
+use crate::staging::write_atomically;
+use flate2::write::GzEncoder;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Where the files of a run end up, each one at a path relative to the root of the output
+pub trait OutputSink {
+    fn write(&self, path: &Path, bytes: &[u8]) -> Fallible<()>;
+    /// The content of the file at `path`, `None` when there is no such file
+    fn read(&self, path: &Path) -> Fallible<Option<Vec<u8>>>;
+    /// Removes the file at `path`, if there is one
+    fn remove(&self, path: &Path) -> Fallible<()>;
+    /// Called once every file of the run has been written
+    fn finish(&self) -> Fallible<()> {
+        Ok(())
+    }
+}
+
+/// Writes every file atomically under a directory
+pub struct FsSink {
+    root: PathBuf,
+}
+
+/// Keeps every file in memory, clones share the same files so one can be handed to the
+/// generator while the other is used to look at what was written
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    files: Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// Collects every file in memory and writes them all as a single archive when finished
+pub struct ArchiveSink {
+    path: PathBuf,
+    format: ArchiveFormat,
+    /// The directory the files are put in inside of the archive
+    root: PathBuf,
+    files: MemorySink,
+}
+
+impl FsSink {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl OutputSink for FsSink {
+    fn write(&self, path: &Path, bytes: &[u8]) -> Fallible<()> {
+        write_atomically(&self.root.join(path), bytes)
+    }
+
+    fn read(&self, path: &Path) -> Fallible<Option<Vec<u8>>> {
+        match fs::read(self.root.join(path)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn remove(&self, path: &Path) -> Fallible<()> {
+        match fs::remove_file(self.root.join(path)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MemorySink {
+    /// Every file written so far, by path
+    pub fn files(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, Vec<u8>>> {
+        // The files stay consistent even if a writer panicked
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&self, path: &Path, bytes: &[u8]) -> Fallible<()> {
+        self.lock().insert(path.to_path_buf(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn read(&self, path: &Path) -> Fallible<Option<Vec<u8>>> {
+        Ok(self.lock().get(path).cloned())
+    }
+
+    fn remove(&self, path: &Path) -> Fallible<()> {
+        self.lock().remove(path);
+        Ok(())
+    }
+}
+
+impl ArchiveFormat {
+    /// Picks the format out of the extension of `path`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+impl ArchiveSink {
+    /// Prepares an archive at `path`, a `.tar`, `.tar.gz` or `.zip` file, with every file under
+    /// `root` inside of it
+    pub fn new(path: PathBuf, root: PathBuf) -> Fallible<Self> {
+        let Some(format) = ArchiveFormat::from_path(&path) else {
+            anyhow::bail!("{} is not a .tar, .tar.gz or .zip archive", path.display());
+        };
+        Ok(Self {
+            path,
+            format,
+            root,
+            files: MemorySink::default(),
+        })
+    }
+
+    /// Adds every file under `dir` to the archive, e.g. the outputs of the other years
+    pub fn seed_from_dir(&self, dir: &Path) -> Fallible<()> {
+        self.seed_from(dir, dir)
+    }
+
+    fn seed_from(&self, base: &Path, dir: &Path) -> Fallible<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                self.seed_from(base, &entry.path())?;
+            } else {
+                let path = entry.path();
+                self.files
+                    .write(path.strip_prefix(base)?, &fs::read(&path)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&self) -> Fallible<Vec<u8>> {
+        let files = self.files.files();
+        match self.format {
+            ArchiveFormat::Tar => Self::encode_tar(files, &self.root, vec![]),
+            ArchiveFormat::TarGz => {
+                let encoder = GzEncoder::new(vec![], flate2::Compression::best());
+                Ok(Self::encode_tar(files, &self.root, encoder)?.finish()?)
+            }
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+                let options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                for (path, bytes) in files {
+                    // Zip entries always use forward slashes
+                    let name = self.root.join(path).to_string_lossy().replace('\\', "/");
+                    zip.start_file(name, options)?;
+                    zip.write_all(&bytes)?;
+                }
+                Ok(zip.finish()?.into_inner())
+            }
+        }
+    }
+
+    fn encode_tar<W: Write>(
+        files: BTreeMap<PathBuf, Vec<u8>>,
+        root: &Path,
+        writer: W,
+    ) -> Fallible<W> {
+        let mut tar = tar::Builder::new(writer);
+        for (path, bytes) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            // Left at the epoch so that the same files always make the same archive
+            header.set_mtime(0);
+            tar.append_data(&mut header, root.join(path), bytes.as_slice())?;
+        }
+        Ok(tar.into_inner()?)
+    }
+}
+
+impl OutputSink for ArchiveSink {
+    fn write(&self, path: &Path, bytes: &[u8]) -> Fallible<()> {
+        self.files.write(path, bytes)
+    }
+
+    fn read(&self, path: &Path) -> Fallible<Option<Vec<u8>>> {
+        self.files.read(path)
+    }
+
+    fn remove(&self, path: &Path) -> Fallible<()> {
+        self.files.remove(path)
+    }
+
+    fn finish(&self) -> Fallible<()> {
+        write_atomically(&self.path, &self.encode()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_sink_clones_share_their_files() {
+        let sink = MemorySink::default();
+        let writer: Box<dyn OutputSink> = Box::new(sink.clone());
+        writer
+            .write(Path::new("year/days/2025.json"), b"{}")
+            .unwrap();
+        writer.write(Path::new("sha1/2025.json"), b"[]").unwrap();
+        writer.remove(Path::new("sha1/2025.json")).unwrap();
+        writer.remove(Path::new("missing.json")).unwrap();
+        assert_eq!(
+            sink.files(),
+            BTreeMap::from([(PathBuf::from("year/days/2025.json"), b"{}".to_vec())])
+        );
+        assert_eq!(
+            writer.read(Path::new("year/days/2025.json")).unwrap(),
+            Some(b"{}".to_vec())
+        );
+        assert_eq!(writer.read(Path::new("missing.json")).unwrap(), None);
+    }
+
+    #[test]
+    fn archive_format_follows_the_extension() {
+        let format = |path: &str| ArchiveFormat::from_path(Path::new(path));
+        assert_eq!(format("out/v1.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("out/v1.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("out/v1.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("out/v1.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(format("out/v1"), None);
+    }
+}
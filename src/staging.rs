@@ -0,0 +1,144 @@
+use crate::prelude::Fallible;
+use anyhow::Context;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A sibling directory of the final output where a whole run is built before it gets swapped into
+/// place, so a failing run never leaves a half old, half new output directory behind
+pub struct StagingDir {
+    target: PathBuf,
+    staging: PathBuf,
+    committed: bool,
+}
+
+impl StagingDir {
+    /// Prepares the staging directory for `target`, seeded with whatever `target` already
+    /// contains so that outputs of other years survive the swap
+    ///
+    /// The files are hard linked instead of copied when the filesystem allows it, which is safe
+    /// as long as they are only ever replaced through [`write_atomically`], never written in
+    /// place.
+    pub fn new(target: PathBuf) -> Fallible<Self> {
+        let staging = Self::sibling(&target, "staging");
+        if staging.exists() {
+            // Leftover from a run that crashed before it could clean up after itself
+            fs::remove_dir_all(&staging)?;
+        }
+        if target.exists() {
+            link_dir_all(&target, &staging)
+                .with_context(|| format!("failed to stage {}", target.display()))?;
+        } else {
+            fs::create_dir_all(&staging)?;
+        }
+        Ok(Self {
+            target,
+            staging,
+            committed: false,
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.staging.clone()
+    }
+
+    /// Swaps the staging directory into the place of the target directory
+    pub fn commit(mut self) -> Fallible<()> {
+        let backup = Self::sibling(&self.target, "old");
+        if backup.exists() {
+            fs::remove_dir_all(&backup)?;
+        }
+        if self.target.exists() {
+            fs::rename(&self.target, &backup)?;
+        }
+        if let Err(e) = fs::rename(&self.staging, &self.target) {
+            if backup.exists() {
+                fs::rename(&backup, &self.target)?;
+            }
+            return Err(e).with_context(|| format!("failed to replace {}", self.target.display()));
+        }
+        self.committed = true;
+        if backup.exists() {
+            fs::remove_dir_all(&backup)?;
+        }
+        Ok(())
+    }
+
+    fn sibling(target: &Path, suffix: &str) -> PathBuf {
+        let mut name = OsString::from(".");
+        name.push(target.file_name().unwrap_or_default());
+        name.push(format!(".{suffix}"));
+        target.with_file_name(name)
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.staging);
+        }
+    }
+}
+
+/// Writes `contents` to `path` through a temporary file that is renamed over the final path, so
+/// readers never observe a truncated file
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Fallible<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    // A leftover of a crashed run may be linked to the one of the previous output
+    let _ = fs::remove_file(&tmp_path);
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn link_dir_all(from: &Path, to: &Path) -> Fallible<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            link_dir_all(&entry.path(), &dest)?;
+        } else if fs::hard_link(entry.path(), &dest).is_err() {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staged_files_only_replace_the_target_once_committed() {
+        let dir = std::env::temp_dir().join(format!("ptig-staging-{}", std::process::id()));
+        let target = dir.join("v1");
+        write_atomically(&target.join("year/days/2024.json"), b"2024").unwrap();
+        write_atomically(&target.join("year/days/2025.json"), b"old").unwrap();
+
+        let staging = StagingDir::new(target.clone()).unwrap();
+        write_atomically(&staging.path().join("year/days/2025.json"), b"new").unwrap();
+        assert_eq!(
+            fs::read(target.join("year/days/2025.json")).unwrap(),
+            b"old"
+        );
+        staging.commit().unwrap();
+
+        assert_eq!(
+            fs::read(target.join("year/days/2024.json")).unwrap(),
+            b"2024"
+        );
+        assert_eq!(
+            fs::read(target.join("year/days/2025.json")).unwrap(),
+            b"new"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use super::domain::Calendar;
+use super::domain::Event;
+use super::domain::EventCategory;
+use super::domain::RecurringEvent;
+
+const MUHARRAM: u8 = 1;
+const RABI_AL_AWWAL: u8 = 3;
+const RAJAB: u8 = 7;
+const SHABAN: u8 = 8;
+pub const RAMADAN: u8 = 9;
+const SHAWWAL: u8 = 10;
+const DHU_AL_HIJJAH: u8 = 12;
+
+/// The occasions that fall on the same hijri date every year
+pub fn builtin_hijri_events() -> Vec<RecurringEvent> {
+    let mut events = vec![
+        occasion(
+            (MUHARRAM, 1),
+            (MUHARRAM, 1),
+            EventCategory::Other,
+            "رأس السنة الهجرية",
+            "Islamic New Year",
+        ),
+        occasion(
+            (MUHARRAM, 10),
+            (MUHARRAM, 10),
+            EventCategory::Fasting,
+            "عاشوراء",
+            "Ashura",
+        ),
+        occasion(
+            (RABI_AL_AWWAL, 12),
+            (RABI_AL_AWWAL, 12),
+            EventCategory::Other,
+            "المولد النبوي الشريف",
+            "Mawlid an-Nabi",
+        ),
+        occasion(
+            (RAJAB, 27),
+            (RAJAB, 27),
+            EventCategory::Other,
+            "الإسراء والمعراج",
+            "Isra and Mi'raj",
+        ),
+        occasion(
+            (SHABAN, 15),
+            (SHABAN, 15),
+            EventCategory::Other,
+            "ليلة النصف من شعبان",
+            "Mid-Sha'ban",
+        ),
+        occasion(
+            (RAMADAN, 1),
+            (RAMADAN, 1),
+            EventCategory::Fasting,
+            "بداية شهر رمضان",
+            "First day of Ramadan",
+        ),
+        occasion(
+            (RAMADAN, 27),
+            (RAMADAN, 27),
+            EventCategory::Other,
+            "ليلة القدر",
+            "Laylat al-Qadr",
+        ),
+        occasion(
+            (SHAWWAL, 1),
+            (SHAWWAL, 3),
+            EventCategory::Eid,
+            "عيد الفطر",
+            "Eid al-Fitr",
+        ),
+        occasion(
+            (DHU_AL_HIJJAH, 9),
+            (DHU_AL_HIJJAH, 9),
+            EventCategory::Fasting,
+            "يوم عرفة",
+            "Day of Arafah",
+        ),
+        occasion(
+            (DHU_AL_HIJJAH, 10),
+            (DHU_AL_HIJJAH, 13),
+            EventCategory::Eid,
+            "عيد الأضحى",
+            "Eid al-Adha",
+        ),
+    ];
+    // The white days are not fasted during Ramadan, nor on the days of Eid al-Adha
+    for month in (1..=12).filter(|month| ![RAMADAN, DHU_AL_HIJJAH].contains(month)) {
+        events.push(occasion(
+            (month, 13),
+            (month, 15),
+            EventCategory::Fasting,
+            "الأيام البيض",
+            "The white days",
+        ));
+    }
+    events
+}
+
+fn occasion(
+    start: (u8, u8),
+    end: (u8, u8),
+    category: EventCategory,
+    ar: &str,
+    en: &str,
+) -> RecurringEvent {
+    RecurringEvent {
+        calendar: Calendar::Hijri,
+        start,
+        end,
+        event: Event {
+            ar: ar.to_string(),
+            en: Some(en.to_string()),
+            category: Some(category),
+        },
+    }
+}
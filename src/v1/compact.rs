@@ -0,0 +1,95 @@
+use super::domain::minutes_since_midnight;
+use super::domain::DailyPrayerTime;
+
+/// Marks a prayer time that could not be parsed
+pub const MISSING_TIME: u16 = u16::MAX;
+
+/// Encodes the year as a fixed-width binary table for clients too small to parse json
+///
+/// All integers are little endian. The header is the `PTIG` magic, a `u8` format version, the
+/// `u16` year and the `u16` number of days. Each day then takes 14 bytes: the `u8` month, the `u8`
+/// day of the month, and the fajr, sunrise, dhuhr, asr, maghrib and ishaa times as `u16` minutes
+/// since midnight.
+pub fn encode_year(year: u16, days: &[DailyPrayerTime]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(9 + days.len() * 14);
+    bytes.extend_from_slice(b"PTIG");
+    bytes.push(1);
+    bytes.extend_from_slice(&year.to_le_bytes());
+    bytes.extend_from_slice(&(days.len() as u16).to_le_bytes());
+    for day in days {
+        bytes.push(day.gregorian_date.month);
+        bytes.push(day.gregorian_date.day as u8);
+        let times = &day.prayer_times;
+        for time in [
+            &times.fajr,
+            &times.sunrise,
+            &times.dhuhr,
+            &times.asr,
+            &times.maghrib,
+            &times.ishaa,
+        ] {
+            let minutes = minutes_since_midnight(time).unwrap_or(MISSING_TIME);
+            bytes.extend_from_slice(&minutes.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::domain::GregorianDate;
+    use crate::v1::domain::PrayerTimes;
+
+    fn day(month: u8, day: u16, times: [&str; 6]) -> DailyPrayerTime {
+        let [fajr, sunrise, dhuhr, asr, maghrib, ishaa] = times.map(String::from);
+        DailyPrayerTime {
+            gregorian_date: GregorianDate {
+                index: 1,
+                day,
+                month,
+                year: 2025,
+            },
+            hijri_date: "1/7/1446".to_string(),
+            prayer_times: PrayerTimes {
+                fajr,
+                sunrise,
+                dhuhr,
+                asr,
+                maghrib,
+                ishaa,
+            },
+            week_id: None,
+            hadith: None,
+            content: None,
+            display: None,
+            events: vec![],
+        }
+    }
+
+    #[test]
+    fn year_is_encoded_as_a_header_and_fixed_width_days() {
+        let days = [
+            day(1, 1, ["05:40", "07:00", "12:00", "14:30", "16:50", "18:10"]),
+            day(12, 31, ["00:00", "07:05", "12:05", "?", "16:55", "23:59"]),
+        ];
+        let bytes = encode_year(2025, &days);
+        assert_eq!(bytes.len(), 9 + 2 * 14);
+        assert_eq!(&bytes[..4], b"PTIG");
+        assert_eq!(bytes[4], 1);
+        assert_eq!(&bytes[5..9], [0xE9, 0x07, 2, 0]);
+        let minutes = |bytes: &[u8]| {
+            bytes
+                .chunks(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(&bytes[9..11], [1, 1]);
+        assert_eq!(minutes(&bytes[11..23]), [340, 420, 720, 870, 1010, 1090]);
+        assert_eq!(&bytes[23..25], [12, 31]);
+        assert_eq!(
+            minutes(&bytes[25..37]),
+            [0, 425, 725, MISSING_TIME, 1015, 1439]
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::prelude::Fallible;
+use flate2::write::GzEncoder;
+use std::io::Write;
+
+/// Precompressed sidecars written next to every output file, e.g. for nginx's `gzip_static`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Compression {
+    Gzip,
+    Brotli,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 2] = [Compression::Gzip, Compression::Brotli];
+
+    /// The extension appended to the name of the compressed file
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Brotli => "br",
+        }
+    }
+
+    /// The name of the encoding in the `Content-Encoding` and `Accept-Encoding` headers
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Brotli => "br",
+        }
+    }
+
+    pub fn compress(&self, bytes: &[u8]) -> Fallible<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], flate2::Compression::best());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Brotli => {
+                let mut compressed = vec![];
+                let params = brotli::enc::BrotliEncoderParams::default();
+                brotli::BrotliCompress(&mut &bytes[..], &mut compressed, &params)?;
+                Ok(compressed)
+            }
+        }
+    }
+}
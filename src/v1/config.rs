@@ -0,0 +1,205 @@
+use super::compression::Compression;
+use super::domain::Prayer;
+use super::domain::WeekDay;
+use super::domain::WeekScheme;
+use super::index::IndexKind;
+use super::input_source::UnknownColumns;
+use super::localization::Locale;
+use super::localization::Numerals;
+use super::localization::TimeFormat;
+use super::output_format::OutputFormat;
+use super::params::InputClock;
+use super::params::InputFormat;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A `ptig.toml` project file
+///
+/// Its top-level settings apply to every region, each `[regions.<name>]` table overriding them for
+/// its region. The paths can hold `{year}` and `{region}` placeholders and are relative to the
+/// file.
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub settings: ConfigSettings,
+    /// The settings of each region, already merged over the top-level ones
+    pub regions: BTreeMap<String, ConfigSettings>,
+}
+
+/// The settings of a `ptig.toml`, named after the flags of `ptig v1` and all optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigSettings {
+    pub years: Option<Years>,
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub archive: Option<String>,
+    #[serde(deserialize_with = "value_enum")]
+    pub format: Option<InputFormat>,
+    #[serde(deserialize_with = "value_enum")]
+    pub unknown_columns: Option<UnknownColumns>,
+    #[serde(deserialize_with = "value_enum")]
+    pub input_clock: Option<InputClock>,
+    #[serde(deserialize_with = "value_enum")]
+    pub week_start_day: Option<WeekDay>,
+    #[serde(deserialize_with = "value_enum")]
+    pub week_scheme: Option<WeekScheme>,
+    pub no_builtin_events: Option<bool>,
+    pub imsak_offset: Option<u16>,
+    #[serde(deserialize_with = "value_enums")]
+    pub output_formats: Option<Vec<OutputFormat>>,
+    pub compact_year: Option<bool>,
+    pub sqlite: Option<bool>,
+    #[serde(deserialize_with = "value_enums")]
+    pub indexes: Option<Vec<IndexKind>>,
+    pub minify: Option<bool>,
+    #[serde(deserialize_with = "value_enums")]
+    pub compress: Option<Vec<Compression>>,
+    #[serde(deserialize_with = "value_enums")]
+    pub locales: Option<Vec<Locale>>,
+    #[serde(deserialize_with = "value_enum")]
+    pub numerals: Option<Numerals>,
+    #[serde(deserialize_with = "value_enum")]
+    pub time_format: Option<TimeFormat>,
+    /// Minutes added to each prayer time, like `--adjust`
+    pub adjustments: BTreeMap<Prayer, i16>,
+}
+
+/// The years to generate: `2026`, `[2025, 2026]` or `"2025-2027"`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Years {
+    One(u16),
+    List(Vec<u16>),
+    Range(String),
+}
+
+impl ConfigFile {
+    pub const FILE_NAME: &'static str = "ptig.toml";
+
+    /// Reads `path`, or the `ptig.toml` of the current directory when there is one
+    pub fn discover(path: Option<&Path>) -> Fallible<Option<Self>> {
+        match path {
+            Some(path) => Self::load(path).map(Some),
+            None if Path::new(Self::FILE_NAME).is_file() => {
+                Self::load(Path::new(Self::FILE_NAME)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Fallible<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut table: toml::Table =
+            toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))?;
+        let region_tables = match table.remove("regions") {
+            Some(toml::Value::Table(regions)) => regions,
+            Some(_) => anyhow::bail!("{}: regions must be a table", path.display()),
+            None => toml::Table::new(),
+        };
+        let parse = |table: toml::Table, name: &str| -> Fallible<ConfigSettings> {
+            table
+                .try_into()
+                .with_context(|| format!("invalid {name} in {}", path.display()))
+        };
+        let mut regions = BTreeMap::new();
+        for (name, region) in region_tables {
+            let toml::Value::Table(region) = region else {
+                anyhow::bail!("{}: regions.{name} must be a table", path.display());
+            };
+            let mut merged = table.clone();
+            merged.extend(region);
+            regions.insert(name.clone(), parse(merged, &format!("regions.{name}"))?);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            settings: parse(table, "settings")?,
+            regions,
+        })
+    }
+
+    /// The settings of every run of the file, along with the name of their region: the ones of
+    /// `region` when given, otherwise the ones of every region, or the top-level ones when there
+    /// are no regions
+    pub fn runs(&self, region: Option<&str>) -> Fallible<Vec<(Option<&str>, &ConfigSettings)>> {
+        match region {
+            Some(region) => match self.regions.get_key_value(region) {
+                Some((name, settings)) => Ok(vec![(Some(name.as_str()), settings)]),
+                None => anyhow::bail!(
+                    "there is no region {region:?} in {}, only {:?}",
+                    self.path.display(),
+                    self.regions.keys().collect::<Vec<_>>()
+                ),
+            },
+            None if self.regions.is_empty() => Ok(vec![(None, &self.settings)]),
+            None => Ok(self
+                .regions
+                .iter()
+                .map(|(name, settings)| (Some(name.as_str()), settings))
+                .collect()),
+        }
+    }
+
+    /// `template` with its placeholders filled, relative to the directory of the file
+    pub fn path(&self, template: &str, region: Option<&str>, year: u16) -> PathBuf {
+        let path = template
+            .replace("{year}", &year.to_string())
+            .replace("{region}", region.unwrap_or_default());
+        match self.path.parent() {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+}
+
+impl Years {
+    pub fn list(&self) -> Fallible<Vec<u16>> {
+        match self {
+            Years::One(year) => Ok(vec![*year]),
+            Years::List(years) => Ok(years.clone()),
+            Years::Range(range) => {
+                // A single year is a range of its own
+                let (from, to) = range.split_once('-').unwrap_or((range, range));
+                let parsed = (from.trim().parse::<u16>(), to.trim().parse::<u16>());
+                match parsed {
+                    (Ok(from), Ok(to)) if from <= to => Ok((from..=to).collect()),
+                    _ => anyhow::bail!("{range:?} is not a range of years like \"2025-2027\""),
+                }
+            }
+        }
+    }
+}
+
+/// Reads an option the way clap reads the flag it stands for
+fn value_enum<'de, D: Deserializer<'de>, T: ValueEnum>(d: D) -> Result<Option<T>, D::Error> {
+    let value = String::deserialize(d)?;
+    parse_value(&value).map(Some)
+}
+
+fn value_enums<'de, D: Deserializer<'de>, T: ValueEnum>(d: D) -> Result<Option<Vec<T>>, D::Error> {
+    let values = Vec::<String>::deserialize(d)?;
+    values
+        .iter()
+        .map(|value| parse_value(value))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn parse_value<T: ValueEnum, E: Error>(value: &str) -> Result<T, E> {
+    T::from_str(value, true).map_err(|_| {
+        let names = T::value_variants()
+            .iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|variant| variant.get_name().to_string())
+            .collect::<Vec<_>>();
+        E::custom(format!("{value:?} is not one of {}", names.join(", ")))
+    })
+}
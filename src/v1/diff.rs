@@ -0,0 +1,313 @@
+use super::domain::minutes_since_midnight;
+use super::domain::Week;
+use super::lookup::OutputReader;
+use super::output_dtos::DayOutputDto;
+use super::output_dtos::EventOutputDto;
+use super::output_dtos::HaidthOutputDto;
+use super::output_dtos::YearOutputDto;
+use super::output_dtos::YearWeeksOutputDto;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::File;
+use std::path::Path;
+
+/// Semantic difference between a previously generated year and the one about to be generated
+#[derive(Debug, Default)]
+pub struct YearDiff {
+    pub days: BTreeMap<u64, DayDiff>,
+    pub weeks: BTreeMap<u64, Vec<HadithChange>>,
+}
+
+#[derive(Debug, Default)]
+pub struct DayDiff {
+    pub gregorian: String,
+    pub changes: Vec<DayChange>,
+}
+
+#[derive(Debug)]
+pub enum DayChange {
+    Added,
+    Removed,
+    PrayerTime {
+        prayer: &'static str,
+        old: String,
+        new: String,
+    },
+    EventAdded(EventOutputDto),
+    EventRemoved(EventOutputDto),
+}
+
+#[derive(Debug)]
+pub enum HadithChange {
+    Added(HaidthOutputDto),
+    Removed(HaidthOutputDto),
+}
+
+impl YearDiff {
+    /// Compares `days` and the hadith of `weeks` against the `year` files found in
+    /// `old_output_dir`
+    pub fn against(
+        old_output_dir: &Path,
+        year: u16,
+        days: Vec<DayOutputDto>,
+        weeks: &[Week],
+    ) -> Fallible<Self> {
+        let old_dir = OutputReader::new(old_output_dir).v1_dir;
+        let year_path = pathbuf![&old_dir, "year", "days", format!("{year}.json")];
+        let old_year: YearOutputDto = serde_json::from_reader(
+            File::open(&year_path)
+                .with_context(|| format!("failed to open {}", year_path.display()))?,
+        )
+        .with_context(|| format!("failed to parse {}", year_path.display()))?;
+        let weeks_path = pathbuf![&old_dir, "year", "weeks", format!("{year}.json")];
+        let old_weeks = match File::open(weeks_path) {
+            Ok(file) => serde_json::from_reader::<_, YearWeeksOutputDto>(file)?.weeks,
+            Err(_) => vec![],
+        };
+
+        let mut diff = Self::default();
+        let mut old_days = old_year
+            .year
+            .into_iter()
+            .map(|day| (day.id, day))
+            .collect::<HashMap<_, _>>();
+        for new_day in days {
+            let changes = match old_days.remove(&new_day.id) {
+                Some(old_day) => Self::diff_day(&old_day, &new_day),
+                None => vec![DayChange::Added],
+            };
+            diff.push_day(&new_day, changes);
+        }
+        for old_day in old_days.into_values() {
+            diff.push_day(&old_day, vec![DayChange::Removed]);
+        }
+
+        // Weeks only found in one of the layouts, e.g. after changing the week scheme, are
+        // compared against no hadith
+        let mut hadiths =
+            BTreeMap::<u64, (Option<HaidthOutputDto>, Option<HaidthOutputDto>)>::new();
+        for week in old_weeks {
+            hadiths.entry(week.id).or_default().0 = week.hadith;
+        }
+        for week in weeks {
+            hadiths.entry(week.id).or_default().1 = week.hadith.clone().map(Into::into);
+        }
+        for (id, (old_hadith, new_hadith)) in hadiths {
+            if old_hadith == new_hadith {
+                continue;
+            }
+            let changes = diff.weeks.entry(id).or_default();
+            if let Some(old_hadith) = old_hadith {
+                changes.push(HadithChange::Removed(old_hadith));
+            }
+            if let Some(new_hadith) = new_hadith {
+                changes.push(HadithChange::Added(new_hadith));
+            }
+        }
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty() && self.weeks.is_empty()
+    }
+
+    fn push_day(&mut self, day: &DayOutputDto, changes: Vec<DayChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.days.insert(
+            day.id,
+            DayDiff {
+                gregorian: day.gregorian.clone(),
+                changes,
+            },
+        );
+    }
+
+    fn diff_day(old: &DayOutputDto, new: &DayOutputDto) -> Vec<DayChange> {
+        let mut changes = vec![];
+        let old_times = old.prayer_times.named();
+        for ((prayer, old_time), (_, new_time)) in old_times.iter().zip(new.prayer_times.named()) {
+            if *old_time != new_time {
+                changes.push(DayChange::PrayerTime {
+                    prayer,
+                    old: old_time.to_string(),
+                    new: new_time.to_string(),
+                });
+            }
+        }
+        let old_events = old.all_events();
+        let new_events = new.all_events();
+        for event in &old_events {
+            if !new_events.contains(event) {
+                changes.push(DayChange::EventRemoved(event.clone()));
+            }
+        }
+        for event in new_events {
+            if !old_events.contains(&event) {
+                changes.push(DayChange::EventAdded(event));
+            }
+        }
+        changes
+    }
+}
+
+impl Display for YearDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for day in self.days.values() {
+            writeln!(f, "Day {}:", day.gregorian)?;
+            for change in &day.changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        for (id, changes) in &self.weeks {
+            writeln!(f, "Week {id}:")?;
+            for change in changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for DayChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DayChange::Added => write!(f, "+ day added"),
+            DayChange::Removed => write!(f, "- day removed"),
+            DayChange::PrayerTime { prayer, old, new } => {
+                write!(f, "~ {prayer} {old} -> {new}")?;
+                if let (Some(old), Some(new)) =
+                    (minutes_since_midnight(old), minutes_since_midnight(new))
+                {
+                    write!(f, " ({:+} min)", i32::from(new) - i32::from(old))?;
+                }
+                Ok(())
+            }
+            DayChange::EventAdded(event) => write!(f, "+ event {}", EventDisplay(event)),
+            DayChange::EventRemoved(event) => write!(f, "- event {}", EventDisplay(event)),
+        }
+    }
+}
+
+impl Display for HadithChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HadithChange::Added(hadith) => write!(f, "+ hadith {}", hadith.hadith),
+            HadithChange::Removed(hadith) => write!(f, "- hadith {}", hadith.hadith),
+        }
+    }
+}
+
+struct EventDisplay<'a>(&'a EventOutputDto);
+
+impl Display for EventDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0.en {
+            Some(en) => write!(f, "{} ({en})", self.0.ar),
+            None => write!(f, "{}", self.0.ar),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::output_dtos::PrayerTimesOutputDto;
+    use std::fs;
+
+    fn day(id: u64, fajr: &str, events: &[&str]) -> DayOutputDto {
+        DayOutputDto {
+            id,
+            gregorian: format!("{:02}/{:02}/{}", id % 100, id / 100 % 100, id / 10000),
+            hijri: "1/7/1446".to_string(),
+            prayer_times: PrayerTimesOutputDto {
+                fajr: fajr.to_string(),
+                sunrise: "07:00".to_string(),
+                dhuhr: "12:00".to_string(),
+                asr: "14:30".to_string(),
+                maghrib: "16:50".to_string(),
+                ishaa: "18:10".to_string(),
+            },
+            week_id: None,
+            hadith: None,
+            content: None,
+            display: None,
+            event: None,
+            events: events
+                .iter()
+                .map(|ar| EventOutputDto {
+                    ar: ar.to_string(),
+                    en: None,
+                    category: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn changes(old: &DayOutputDto, new: &DayOutputDto) -> Vec<String> {
+        YearDiff::diff_day(old, new)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn prayer_times_are_compared_in_minutes() {
+        let changes = changes(&day(20250101, "05:40", &[]), &day(20250101, "05:43", &[]));
+        assert_eq!(changes, ["~ fajr 05:40 -> 05:43 (+3 min)"]);
+        let unchanged = YearDiff::diff_day(
+            &day(20250101, "05:40", &["a"]),
+            &day(20250101, "05:40", &["a"]),
+        );
+        assert!(unchanged.is_empty());
+    }
+
+    #[test]
+    fn events_are_compared_by_content() {
+        let changes = changes(
+            &day(20250101, "05:40", &["old", "kept"]),
+            &day(20250101, "05:40", &["kept", "new"]),
+        );
+        assert_eq!(changes, ["- event old", "+ event new"]);
+    }
+
+    #[test]
+    fn days_are_compared_against_the_previous_output() {
+        let dir = std::env::temp_dir().join(format!("ptig-diff-{}", std::process::id()));
+        let old_year = YearOutputDto {
+            year: vec![day(20250101, "05:40", &[]), day(20250102, "05:40", &[])],
+            sha1: String::new(),
+        };
+        let year_path = pathbuf![&dir, "v1", "year", "days", "2025.json"];
+        fs::create_dir_all(year_path.parent().unwrap()).unwrap();
+        fs::write(&year_path, serde_json::to_vec(&old_year).unwrap()).unwrap();
+
+        let days = vec![
+            day(20250101, "05:38", &["new"]),
+            day(20250103, "05:40", &[]),
+        ];
+        let diff = YearDiff::against(&dir, 2025, days, &[]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            [
+                "Day 01/01/2025:",
+                "  ~ fajr 05:40 -> 05:38 (-2 min)",
+                "  + event new",
+                "Day 02/01/2025:",
+                "  - day removed",
+                "Day 03/01/2025:",
+                "  + day added",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
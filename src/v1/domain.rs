@@ -1,5 +1,12 @@
+use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::Weekday;
+use serde::Deserialize;
+use serde::Serialize;
 use std::cmp::Ordering;
 use std::fmt::Display;
+use std::ops::Range;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DailyPrayerTime {
@@ -7,7 +14,20 @@ pub struct DailyPrayerTime {
     pub hijri_date: String,
     pub prayer_times: PrayerTimes,
     pub week_id: Option<u64>,
-    pub event: Option<Event>,
+    /// The hadith of the week the day belongs to
+    pub hadith: Option<Hadith>,
+    /// The reminder shown on the day, from `daily_content.csv`
+    pub content: Option<DailyContent>,
+    /// The dates and prayer times of the day as shown to people
+    pub display: Option<DayDisplay>,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayDisplay {
+    pub gregorian: String,
+    pub hijri: String,
+    pub prayer_times: PrayerTimes,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
@@ -20,6 +40,24 @@ pub struct PrayerTimes {
     pub ishaa: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Prayer {
+    Fajr,
+    Sunrise,
+    Dhuhr,
+    Asr,
+    Maghrib,
+    Ishaa,
+}
+
+/// Minutes added to a prayer time of every day, e.g. for a city near the one of the timetable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adjustment {
+    pub prayer: Prayer,
+    pub minutes: i16,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GregorianDate {
     pub index: u16,
@@ -28,10 +66,45 @@ pub struct GregorianDate {
     pub year: u16,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HijriDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct Event {
     pub ar: String,
     pub en: Option<String>,
+    pub category: Option<EventCategory>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    Eid,
+    Fasting,
+    PublicHoliday,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Calendar {
+    Gregorian,
+    Hijri,
+}
+
+/// An event that comes back every year on the same day, or range of days, of a calendar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurringEvent {
+    pub calendar: Calendar,
+    /// The first day of the event as `(month, day)`
+    pub start: (u8, u8),
+    /// The last day of the event as `(month, day)`, the same as `start` for single day events
+    pub end: (u8, u8),
+    pub event: Event,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
@@ -40,15 +113,318 @@ pub struct Hadith {
     pub note: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HadithStart {
+    /// The number of the week in the year, only stable for a given week layout
+    Week(u16),
+    Gregorian(NaiveDate),
+    Hijri(HijriDate),
+}
+
+/// A hadith along with the day of the week it is assigned to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeeklyHadith {
+    pub start: HadithStart,
+    pub hadith: Hadith,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct DailyContent {
+    pub kind: Option<DailyContentKind>,
+    pub ar: String,
+    pub translation: Option<String>,
+    /// Where the text comes from, e.g. the surah and ayah numbers
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DailyContentKind {
+    Hadith,
+    Ayah,
+    Dua,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DailyContentKey {
+    Gregorian(NaiveDate),
+    Hijri(HijriDate),
+    /// The position of the content in the rotation filling the days without a dated content
+    Rotation(usize),
+}
+
+/// A daily content along with the day, or the days, it is shown on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyedDailyContent {
+    pub key: DailyContentKey,
+    pub content: DailyContent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum WeekDay {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum WeekScheme {
+    /// Weeks end on the day before the start day, starting from the 1st of January, so the first
+    /// and last weeks may be partial
+    Chunked,
+    /// ISO-8601 weeks, starting on Monday and numbered within the ISO year, which ignores the
+    /// start day
+    Iso,
+    /// Only the weeks that start on the start day and end within the year, the days before the
+    /// first and after the last of them have no week
+    Contained,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Week {
+    pub id: u64,
+    /// The number of the week in the year, the last two digits of the id
+    pub number: u16,
+    /// The indexes of the days of the week in the days of the year
+    pub days: Range<usize>,
+    /// The first day of the week, which can be in the previous year
+    pub start: Option<NaiveDate>,
+    /// The last day of the week, which can be in the next year
+    pub end: Option<NaiveDate>,
+    pub hadith: Option<Hadith>,
+}
+
+impl WeekDay {
+    pub fn previous(&self) -> Self {
+        match self {
+            WeekDay::Sun => WeekDay::Sat,
+            WeekDay::Mon => WeekDay::Sun,
+            WeekDay::Tue => WeekDay::Mon,
+            WeekDay::Wed => WeekDay::Tue,
+            WeekDay::Thu => WeekDay::Wed,
+            WeekDay::Fri => WeekDay::Thu,
+            WeekDay::Sat => WeekDay::Fri,
+        }
+    }
+}
+
+impl DailyPrayerTime {
+    /// The hijri date of the day, if the input has it in the `D/M/YYYY` form
+    pub fn hijri(&self) -> Option<HijriDate> {
+        HijriDate::parse(&self.hijri_date)
+    }
+}
+
+impl EventCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventCategory::Eid => "eid",
+            EventCategory::Fasting => "fasting",
+            EventCategory::PublicHoliday => "public_holiday",
+            EventCategory::Other => "other",
+        }
+    }
+}
+
+impl Prayer {
+    pub const ALL: [Prayer; 6] = [
+        Prayer::Fajr,
+        Prayer::Sunrise,
+        Prayer::Dhuhr,
+        Prayer::Asr,
+        Prayer::Maghrib,
+        Prayer::Ishaa,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Prayer::Fajr => "fajr",
+            Prayer::Sunrise => "sunrise",
+            Prayer::Dhuhr => "dhuhr",
+            Prayer::Asr => "asr",
+            Prayer::Maghrib => "maghrib",
+            Prayer::Ishaa => "ishaa",
+        }
+    }
+}
+
+impl FromStr for Adjustment {
+    type Err = String;
+
+    /// Parses a `prayer=minutes` adjustment like `fajr=-2` or `maghrib=+3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prayer, minutes) = s
+            .split_once('=')
+            .ok_or_else(|| format!("{s:?} is not a prayer=minutes adjustment"))?;
+        let prayer = Prayer::ALL
+            .into_iter()
+            .find(|p| p.as_str() == prayer.trim())
+            .ok_or_else(|| {
+                format!("{prayer:?} is not one of fajr, sunrise, dhuhr, asr, maghrib, ishaa")
+            })?;
+        let minutes = minutes
+            .trim()
+            .parse::<i16>()
+            .map_err(|_| format!("{minutes:?} is not a number of minutes"))?;
+        Ok(Self { prayer, minutes })
+    }
+}
+
+impl PrayerTimes {
+    pub fn get_mut(&mut self, prayer: Prayer) -> &mut String {
+        match prayer {
+            Prayer::Fajr => &mut self.fajr,
+            Prayer::Sunrise => &mut self.sunrise,
+            Prayer::Dhuhr => &mut self.dhuhr,
+            Prayer::Asr => &mut self.asr,
+            Prayer::Maghrib => &mut self.maghrib,
+            Prayer::Ishaa => &mut self.ishaa,
+        }
+    }
+
+    /// Moves the time of the prayer of `adjustment`, `None` when it is not a `HH:MM` time
+    pub fn adjust(&mut self, adjustment: Adjustment) -> Option<()> {
+        let time = self.get_mut(adjustment.prayer);
+        let minutes = minutes_since_midnight(time)?;
+        *time = format_minutes(i32::from(minutes) + i32::from(adjustment.minutes));
+        Some(())
+    }
+
+    /// Turns 12-hour times into `HH:MM` ones, using their AM/PM suffix when they have one and
+    /// otherwise the half of the day each prayer falls in
+    pub fn resolve_12_hour(&mut self) -> Option<()> {
+        self.fajr = to_24_hour(&self.fajr, |hour| if hour == 12 { 0 } else { hour })?;
+        self.sunrise = to_24_hour(&self.sunrise, |hour| if hour == 12 { 0 } else { hour })?;
+        // Dhuhr is around noon, so only 11 can be in the morning
+        self.dhuhr = to_24_hour(&self.dhuhr, |hour| if hour < 11 { hour + 12 } else { hour })?;
+        self.asr = to_24_hour(&self.asr, |hour| if hour < 12 { hour + 12 } else { hour })?;
+        self.maghrib = to_24_hour(
+            &self.maghrib,
+            |hour| if hour < 12 { hour + 12 } else { hour },
+        )?;
+        // Ishaa can be past midnight in the summer of high latitudes
+        self.ishaa = to_24_hour(&self.ishaa, |hour| match hour {
+            12 | 1..=3 => hour % 12,
+            _ => hour + 12,
+        })?;
+        Some(())
+    }
+}
+
+impl DailyContentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DailyContentKind::Hadith => "hadith",
+            DailyContentKind::Ayah => "ayah",
+            DailyContentKind::Dua => "dua",
+        }
+    }
+}
+
+impl RecurringEvent {
+    pub fn occurs_on(&self, day: &DailyPrayerTime) -> bool {
+        let month_day = match self.calendar {
+            Calendar::Gregorian => (day.gregorian_date.month, day.gregorian_date.day as u8),
+            Calendar::Hijri => match day.hijri() {
+                Some(hijri) => (hijri.month, hijri.day),
+                None => return false,
+            },
+        };
+        if self.start <= self.end {
+            self.start <= month_day && month_day <= self.end
+        } else {
+            // The event wraps around the end of the year
+            self.start <= month_day || month_day <= self.end
+        }
+    }
+}
+
+impl GregorianDate {
+    pub fn weekday(&self) -> Option<Weekday> {
+        self.to_naive_date().map(|date| date.weekday())
+    }
+
+    pub fn to_naive_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())
+    }
+}
+
+impl HijriDate {
+    /// Parses a `D/M/YYYY` hijri date
+    pub fn parse(date: &str) -> Option<Self> {
+        let mut components = date.trim().splitn(3, '/');
+        let day = components.next()?.trim().parse().ok()?;
+        let month = components.next()?.trim().parse().ok()?;
+        let year = components.next()?.trim().parse().ok()?;
+        Some(Self { year, month, day })
+    }
+}
+
+impl WeeklyHadith {
+    /// Whether the hadith is the one of the `number`th week, made of `days`
+    pub fn starts_in(&self, number: u16, days: &[DailyPrayerTime]) -> bool {
+        match &self.start {
+            HadithStart::Week(week) => *week == number,
+            HadithStart::Gregorian(date) => days
+                .iter()
+                .any(|day| day.gregorian_date.to_naive_date() == Some(*date)),
+            HadithStart::Hijri(date) => days.iter().any(|day| day.hijri() == Some(*date)),
+        }
+    }
+}
+
+impl KeyedDailyContent {
+    /// Whether the content is dated for `day`, rotated contents are never
+    pub fn is_dated_for(&self, day: &DailyPrayerTime) -> bool {
+        match &self.key {
+            DailyContentKey::Gregorian(date) => day.gregorian_date.to_naive_date() == Some(*date),
+            DailyContentKey::Hijri(date) => day.hijri() == Some(*date),
+            DailyContentKey::Rotation(_) => false,
+        }
+    }
+}
+
+impl From<&WeekDay> for chrono::Weekday {
+    fn from(day: &WeekDay) -> Self {
+        match day {
+            WeekDay::Sun => chrono::Weekday::Sun,
+            WeekDay::Mon => chrono::Weekday::Mon,
+            WeekDay::Tue => chrono::Weekday::Tue,
+            WeekDay::Wed => chrono::Weekday::Wed,
+            WeekDay::Thu => chrono::Weekday::Thu,
+            WeekDay::Fri => chrono::Weekday::Fri,
+            WeekDay::Sat => chrono::Weekday::Sat,
+        }
+    }
+}
+
 impl Display for GregorianDate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{}/{}/{}", self.year, self.month, self.day))
     }
 }
 
+impl Display for HadithStart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HadithStart::Week(week) => write!(f, "on week {week}"),
+            HadithStart::Gregorian(date) => write!(f, "on {}", date.format("%d/%m/%Y")),
+            HadithStart::Hijri(date) => {
+                write!(f, "on {}/{}/{} AH", date.day, date.month, date.year)
+            }
+        }
+    }
+}
+
 impl PartialOrd for GregorianDate {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.index.cmp(&other.index))
+        Some(self.cmp(other))
     }
 }
 
@@ -66,6 +442,165 @@ impl Ord for DailyPrayerTime {
 
 impl PartialOrd for DailyPrayerTime {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.gregorian_date.cmp(&other.gregorian_date))
+        Some(self.cmp(other))
+    }
+}
+
+/// Parses a `D/M` day of the year into `(month, day)`
+pub fn parse_day_month(date: &str) -> Option<(u8, u8)> {
+    let (day, month) = date.trim().split_once('/')?;
+    let day = day.trim().parse::<u8>().ok()?;
+    let month = month.trim().parse::<u8>().ok()?;
+    ((1..=12).contains(&month) && (1..=31).contains(&day)).then_some((month, day))
+}
+
+/// Formats minutes since midnight as a `HH:MM` time, wrapping around midnight
+pub fn format_minutes(minutes: i32) -> String {
+    let minutes = minutes.rem_euclid(24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Parses a `HH:MM` time into the number of minutes since midnight
+pub fn minutes_since_midnight(time: &str) -> Option<u16> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let hours = hours.parse::<u16>().ok()?;
+    let minutes = minutes.parse::<u16>().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Parses a 12-hour `H:MM` time, with an optional AM/PM suffix, into a `HH:MM` one, resolving the
+/// hour of bare times with `bare_hour`
+fn to_24_hour(time: &str, bare_hour: fn(u16) -> u16) -> Option<String> {
+    let time = time.trim();
+    let split_at = time
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(time.len());
+    let (clock, suffix) = time.split_at(split_at);
+    let (hours, minutes) = clock.split_once(':')?;
+    let hours = hours.parse::<u16>().ok()?;
+    let minutes = minutes.parse::<u16>().ok()?;
+    if !(1..=12).contains(&hours) || minutes >= 60 {
+        return None;
+    }
+    let suffix = suffix.trim().to_lowercase().replace('.', "");
+    let hours = match suffix.as_str() {
+        "" => bare_hour(hours),
+        "am" | "ص" => hours % 12,
+        "pm" | "م" => hours % 12 + 12,
+        _ => return None,
+    };
+    Some(format_minutes(i32::from(hours * 60 + minutes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(times: [&str; 6]) -> PrayerTimes {
+        let [fajr, sunrise, dhuhr, asr, maghrib, ishaa] = times.map(String::from);
+        PrayerTimes {
+            fajr,
+            sunrise,
+            dhuhr,
+            asr,
+            maghrib,
+            ishaa,
+        }
+    }
+
+    fn day(month: u8, day: u16, hijri: &str) -> DailyPrayerTime {
+        DailyPrayerTime {
+            gregorian_date: GregorianDate {
+                index: 1,
+                day,
+                month,
+                year: 2025,
+            },
+            hijri_date: hijri.to_string(),
+            prayer_times: times(["04:33", "06:00", "12:00", "15:00", "18:00", "19:30"]),
+            week_id: None,
+            hadith: None,
+            content: None,
+            display: None,
+            events: vec![],
+        }
+    }
+
+    fn event(calendar: Calendar, start: (u8, u8), end: (u8, u8)) -> RecurringEvent {
+        RecurringEvent {
+            calendar,
+            start,
+            end,
+            event: Event {
+                ar: "مناسبة".to_string(),
+                en: None,
+                category: None,
+            },
+        }
+    }
+
+    #[test]
+    fn bare_12_hour_times_are_put_in_the_half_of_their_prayer() {
+        let mut prayer_times = times(["4:33", "6:00", "12:05", "3:15", "5:45", "7:10"]);
+        prayer_times.resolve_12_hour().unwrap();
+        assert_eq!(
+            prayer_times,
+            times(["04:33", "06:00", "12:05", "15:15", "17:45", "19:10"])
+        );
+
+        // Around noon and midnight
+        let mut prayer_times = times(["12:40", "6:00", "11:58", "3:15", "5:45", "12:30"]);
+        prayer_times.resolve_12_hour().unwrap();
+        assert_eq!(
+            prayer_times,
+            times(["00:40", "06:00", "11:58", "15:15", "17:45", "00:30"])
+        );
+    }
+
+    #[test]
+    fn suffixed_12_hour_times_keep_their_half() {
+        let mut prayer_times = times([
+            "4:33 AM",
+            "6:00 a.m.",
+            "1:05 PM",
+            "3:15 م",
+            "5:45pm",
+            "11:10 PM",
+        ]);
+        prayer_times.resolve_12_hour().unwrap();
+        assert_eq!(
+            prayer_times,
+            times(["04:33", "06:00", "13:05", "15:15", "17:45", "23:10"])
+        );
+    }
+
+    #[test]
+    fn invalid_12_hour_times_are_refused() {
+        for invalid in ["13:05", "0:30", "4:60", "4:33 XM", "04h33"] {
+            let mut prayer_times = times([invalid, "6:00", "12:05", "3:15", "5:45", "7:10"]);
+            assert_eq!(prayer_times.resolve_12_hour(), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn events_wrap_around_the_end_of_the_year() {
+        let new_year = event(Calendar::Gregorian, (12, 30), (1, 2));
+        assert!(!new_year.occurs_on(&day(12, 29, "")));
+        assert!(new_year.occurs_on(&day(12, 30, "")));
+        assert!(new_year.occurs_on(&day(12, 31, "")));
+        assert!(new_year.occurs_on(&day(1, 1, "")));
+        assert!(new_year.occurs_on(&day(1, 2, "")));
+        assert!(!new_year.occurs_on(&day(1, 3, "")));
+        assert!(!new_year.occurs_on(&day(6, 15, "")));
+    }
+
+    #[test]
+    fn hijri_events_wrap_around_the_end_of_the_hijri_year() {
+        let hijri_new_year = event(Calendar::Hijri, (12, 29), (1, 1));
+        assert!(hijri_new_year.occurs_on(&day(6, 25, "29/12/1446")));
+        assert!(hijri_new_year.occurs_on(&day(6, 26, "1/1/1447")));
+        assert!(!hijri_new_year.occurs_on(&day(6, 27, "2/1/1447")));
+        // Days without a hijri date never have hijri events
+        assert!(!hijri_new_year.occurs_on(&day(6, 26, "")));
     }
 }
@@ -1,211 +1,737 @@
+use super::catalog::builtin_hijri_events;
+use super::catalog::RAMADAN;
+use super::compact;
+use super::compression::Compression;
 use super::domain;
+use super::domain::parse_day_month;
+use super::domain::Adjustment;
+use super::domain::Calendar;
+use super::domain::DailyContent;
+use super::domain::DailyContentKey;
+use super::domain::DayDisplay;
 use super::domain::Event;
 use super::domain::Hadith;
+use super::domain::HadithStart;
+use super::domain::KeyedDailyContent;
+use super::domain::RecurringEvent;
+use super::domain::Week;
+use super::domain::WeekDay;
+use super::domain::WeekScheme;
+use super::domain::WeeklyHadith;
+use super::input_dtos::DailyContentInputDto;
 use super::input_dtos::DayInputDto;
 use super::input_dtos::EventInputDto;
 use super::input_dtos::WeeklyHadithInputDto;
+use super::input_source::ColumnMapping;
+use super::input_source::CsvDirSource;
+use super::input_source::InputSource;
+use super::input_source::UnknownColumns;
+use super::localization::Locale;
+use super::localization::Numerals;
+use super::localization::TimeFormat;
 use super::output_dtos::*;
-use super::params::WeekDay;
-use crate::pathbuf;
+use super::output_format::OutputFormat;
+use super::sqlite;
 use crate::prelude::Fallible;
+use crate::sink::MemorySink;
+use crate::sink::OutputSink;
 use crate::v1::domain::DailyPrayerTime;
 use crate::v1::domain::GregorianDate;
-use chrono::offset::LocalResult;
+use crate::v1::domain::HijriDate;
+use anyhow::Context;
 use chrono::Datelike;
-use chrono::TimeZone;
-use chrono::Utc;
+use chrono::Days;
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
-use std::collections::HashMap;
-use std::fs;
-use std::fs::read_dir;
-use std::fs::File;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
 use std::path::PathBuf;
 
+/// Days of the year and its neighbours grouped by hijri year and month
+pub type HijriMonths = BTreeMap<(u16, u8), Vec<DayOutputDto>>;
+/// Days of the year and its neighbours grouped by hijri year
+pub type HijriYears = BTreeMap<u16, Vec<DayOutputDto>>;
+
 pub struct Generator {
-    pub output_dir: PathBuf,
+    /// Where the indexes are written, in memory until it is set
+    pub sink: Box<dyn OutputSink>,
     pub yearly_prayer_times: Vec<DailyPrayerTime>,
-    pub weekly_hadith: HashMap<u16, Hadith>,
+    pub events: Vec<RecurringEvent>,
+    pub weekly_hadith: Vec<WeeklyHadith>,
+    /// The weeks of the year, laid out by [`Generator::make_weeks`]
+    pub weeks: Vec<Week>,
+    /// Encodings to write every index in
+    pub formats: Vec<OutputFormat>,
+    /// Also write the year as a fixed-width binary table
+    pub compact_year: bool,
+    /// Write compact json instead of pretty printed json
+    pub minify: bool,
+    /// Precompressed sidecars to write next to every output file
+    pub compressions: Vec<Compression>,
+    /// How many minutes before fajr imsak is in the Ramadan timetable
+    pub imsak_offset: u16,
+    /// The locales to write the labels of
+    pub locales: Vec<Locale>,
+    /// What was left out of the input while loading it, for the caller to report
+    pub warnings: Vec<String>,
 }
 
 impl Generator {
-    pub fn new(year: u16, year_dir: PathBuf, output_dir: PathBuf) -> Fallible<Self> {
-        let input_dir_content = read_dir(&year_dir)?
-            .flatten()
-            .map(|entry| {
-                (
-                    entry.file_name().to_string_lossy().to_string(),
-                    entry.path(),
-                )
-            })
-            .collect::<HashMap<_, _>>();
-        let events = Self::make_events(year_dir.clone())?;
-        let weekly_hadith = Self::make_weekly_hadiths(year_dir);
-        let yearly_prayer_times =
-            Self::make_yearly_prayer_times(year, input_dir_content.clone(), events)?;
-        let this = Self {
-            output_dir,
-            yearly_prayer_times,
-            weekly_hadith,
+    /// Loads the year out of the monthly CSV files of `year_dir` and its side files
+    pub fn new(year: u16, year_dir: PathBuf, builtin_events: bool) -> Fallible<Self> {
+        let columns = ColumnMapping::load(&year_dir, UnknownColumns::default())?;
+        let source = CsvDirSource {
+            dir: year_dir,
+            columns,
+        };
+        Self::from_source(year, &source, builtin_events)
+    }
+
+    /// Loads the year out of `source`, along with the events, hadiths and daily contents of its
+    /// side files
+    pub fn from_source(
+        year: u16,
+        source: &dyn InputSource,
+        builtin_events: bool,
+    ) -> Fallible<Self> {
+        let side_dir = source.side_dir();
+        let explicit_events = match &side_dir {
+            Some(dir) => Self::make_events(pathbuf![dir, "events.csv"], None)?,
+            None => vec![],
+        };
+        let catalog_events =
+            Self::make_catalog_events(side_dir.as_deref(), builtin_events, &explicit_events)?;
+        let daily_content = match &side_dir {
+            Some(dir) => Self::make_daily_contents(dir)?,
+            None => vec![],
         };
+        let weekly_hadith = match side_dir {
+            Some(dir) => Self::make_weekly_hadiths(dir)?,
+            None => vec![],
+        };
+        let mut warnings = vec![];
+        let days = source.read_days(year, &mut warnings)?;
+        let mut yearly_prayer_times = Self::make_yearly_prayer_times(year, days);
+        Self::assign_events(&mut yearly_prayer_times, &explicit_events, &catalog_events);
+        Self::assign_daily_content(&mut yearly_prayer_times, &daily_content)?;
+        let mut this = Self::from_days(yearly_prayer_times);
+        this.events = [explicit_events, catalog_events].concat();
+        this.weekly_hadith = weekly_hadith;
+        this.warnings = warnings;
         Ok(this)
     }
 
-    fn make_events(year_dir: PathBuf) -> Fallible<HashMap<String, Event>> {
-        let events = csv::Reader::from_path(pathbuf![year_dir, "events.csv"])?
-            .deserialize()
-            .flatten()
-            .collect::<Vec<EventInputDto>>();
-        Ok(events
+    /// Makes a generator out of days that were loaded elsewhere, which can render every index in
+    /// memory, and write them to its sink
+    pub fn from_days(yearly_prayer_times: Vec<DailyPrayerTime>) -> Self {
+        Self {
+            sink: Box::new(MemorySink::default()),
+            yearly_prayer_times,
+            events: vec![],
+            weekly_hadith: vec![],
+            weeks: vec![],
+            formats: vec![OutputFormat::Json],
+            compact_year: false,
+            minify: false,
+            compressions: vec![],
+            imsak_offset: 10,
+            locales: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Reads the events of `events_path`, forcing them into `calendar` when it is given
+    fn make_events(
+        events_path: PathBuf,
+        calendar: Option<Calendar>,
+    ) -> Fallible<Vec<RecurringEvent>> {
+        let events = read_rows::<EventInputDto>(&events_path)?;
+        events
             .into_iter()
-            .map(|e| (e.date, Event { ar: e.ar, en: e.en }))
-            .collect())
+            .map(|e| {
+                let start = parse_day_month(&e.date)
+                    .with_context(|| format!("invalid event date {:?}", e.date))?;
+                let end = match &e.until {
+                    Some(until) => parse_day_month(until)
+                        .with_context(|| format!("invalid event end date {until:?}"))?,
+                    None => start,
+                };
+                Ok(RecurringEvent {
+                    calendar: calendar.or(e.calendar).unwrap_or(Calendar::Gregorian),
+                    start,
+                    end,
+                    event: Event {
+                        ar: e.ar,
+                        en: e.en,
+                        category: e.category,
+                    },
+                })
+            })
+            .collect::<Fallible<_>>()
+            .with_context(|| format!("invalid {}", events_path.display()))
     }
 
-    fn make_weekly_hadiths(year_dir: PathBuf) -> HashMap<u16, Hadith> {
-        let Ok(mut reader) = csv::Reader::from_path(pathbuf![year_dir, "weekly_hadith.csv"]) else {
-            return HashMap::new();
+    /// Makes the catalog of hijri occasions out of the built-in one and `hijri_events.csv`
+    ///
+    /// An entry of `hijri_events.csv` replaces the built-in occasion starting on the same day.
+    /// Occasions that `events.csv` already has under the same name are left out, so explicit
+    /// entries always win, e.g. when Eid was announced a day off the printed hijri date.
+    fn make_catalog_events(
+        side_dir: Option<&Path>,
+        builtin_events: bool,
+        explicit_events: &[RecurringEvent],
+    ) -> Fallible<Vec<RecurringEvent>> {
+        let mut catalog = if builtin_events {
+            builtin_hijri_events()
+        } else {
+            vec![]
         };
+        let overrides_path = side_dir.map(|dir| pathbuf![dir, "hijri_events.csv"]);
+        if let Some(overrides_path) = overrides_path.filter(|path| path.exists()) {
+            let overrides = Self::make_events(overrides_path, Some(Calendar::Hijri))?;
+            catalog.retain(|e| !overrides.iter().any(|o| o.start == e.start));
+            catalog.extend(overrides);
+        }
+        catalog.retain(|e| {
+            !explicit_events.iter().any(|explicit| {
+                explicit.event.ar == e.event.ar
+                    || (explicit.event.en.is_some() && explicit.event.en == e.event.en)
+            })
+        });
+        Ok(catalog)
+    }
+
+    /// Attaches the events to the days they occur on, leaving out the catalog events of a
+    /// category that the day already has an explicit event of
+    fn assign_events(
+        days: &mut [DailyPrayerTime],
+        explicit_events: &[RecurringEvent],
+        catalog_events: &[RecurringEvent],
+    ) {
+        for day in days {
+            let mut events = explicit_events
+                .iter()
+                .filter(|e| e.occurs_on(day))
+                .map(|e| e.event.clone())
+                .collect::<Vec<_>>();
+            let catalog = catalog_events
+                .iter()
+                .filter(|e| e.occurs_on(day))
+                .filter(|e| {
+                    e.event.category.is_none()
+                        || !events
+                            .iter()
+                            .any(|explicit| explicit.category == e.event.category)
+                })
+                .map(|e| e.event.clone())
+                .collect::<Vec<_>>();
+            events.extend(catalog);
+            day.events = events;
+        }
+    }
+
+    fn make_weekly_hadiths(year_dir: PathBuf) -> Fallible<Vec<WeeklyHadith>> {
+        let hadiths_path = pathbuf![year_dir, "weekly_hadith.csv"];
+        if !hadiths_path.exists() {
+            return Ok(vec![]);
+        }
 
-        let hadiths = reader
-            .deserialize()
-            .flatten()
-            .collect::<Vec<WeeklyHadithInputDto>>();
+        let hadiths = read_rows::<WeeklyHadithInputDto>(&hadiths_path)?;
         hadiths
             .into_iter()
             .map(|h| {
-                (
-                    h.week,
-                    Hadith {
+                let start = match (h.start, h.week) {
+                    (Some(start), _) => match h.calendar.unwrap_or(Calendar::Gregorian) {
+                        Calendar::Gregorian => NaiveDate::parse_from_str(&start, "%d/%m/%Y")
+                            .map(HadithStart::Gregorian)
+                            .with_context(|| format!("invalid hadith start date {start:?}"))?,
+                        Calendar::Hijri => HijriDate::parse(&start)
+                            .map(HadithStart::Hijri)
+                            .with_context(|| format!("invalid hadith start date {start:?}"))?,
+                    },
+                    (None, Some(week)) => HadithStart::Week(week),
+                    (None, None) => anyhow::bail!("hadith {:?} has no start date", h.hadith),
+                };
+                Ok(WeeklyHadith {
+                    start,
+                    hadith: Hadith {
                         hadith: h.hadith,
                         note: h.note,
                     },
-                )
+                })
+            })
+            .collect::<Fallible<_>>()
+            .with_context(|| format!("invalid {}", hadiths_path.display()))
+    }
+
+    fn make_daily_contents(year_dir: &Path) -> Fallible<Vec<KeyedDailyContent>> {
+        let contents_path = pathbuf![year_dir, "daily_content.csv"];
+        if !contents_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let contents = read_rows::<DailyContentInputDto>(&contents_path)?;
+        contents
+            .into_iter()
+            .map(|c| {
+                let key = match (c.date, c.index) {
+                    (Some(date), _) => match c.calendar.unwrap_or(Calendar::Gregorian) {
+                        Calendar::Gregorian => NaiveDate::parse_from_str(&date, "%d/%m/%Y")
+                            .map(DailyContentKey::Gregorian)
+                            .with_context(|| format!("invalid daily content date {date:?}"))?,
+                        Calendar::Hijri => HijriDate::parse(&date)
+                            .map(DailyContentKey::Hijri)
+                            .with_context(|| format!("invalid daily content date {date:?}"))?,
+                    },
+                    (None, Some(index)) => DailyContentKey::Rotation(index),
+                    (None, None) => anyhow::bail!("daily content {:?} has no date nor index", c.ar),
+                };
+                Ok(KeyedDailyContent {
+                    key,
+                    content: DailyContent {
+                        kind: c.kind,
+                        ar: c.ar,
+                        translation: c.translation,
+                        source: c.source,
+                    },
+                })
+            })
+            .collect::<Fallible<_>>()
+            .with_context(|| format!("invalid {}", contents_path.display()))
+    }
+
+    /// Gives every day its dated content, or the one its position in the year lands on in the
+    /// rotation, making sure the whole year is covered when there are any contents
+    fn assign_daily_content(
+        days: &mut [DailyPrayerTime],
+        contents: &[KeyedDailyContent],
+    ) -> Fallible<()> {
+        if contents.is_empty() {
+            return Ok(());
+        }
+        let mut problems = vec![];
+        let mut rotation = contents
+            .iter()
+            .filter_map(|c| match c.key {
+                DailyContentKey::Rotation(index) => Some((index, &c.content)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        rotation.sort_by_key(|(index, _)| *index);
+        for pair in rotation.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                problems.push(format!(
+                    "rotation index {} is used more than once",
+                    pair[0].0
+                ));
+            }
+        }
+        for (position, day) in days.iter_mut().enumerate() {
+            let dated = contents
+                .iter()
+                .filter(|c| c.is_dated_for(day))
+                .collect::<Vec<_>>();
+            match dated.as_slice() {
+                [content] => day.content = Some(content.content.clone()),
+                [] if !rotation.is_empty() => {
+                    day.content = Some(rotation[position % rotation.len()].1.clone())
+                }
+                [] => problems.push(format!("day {} has no content", day.gregorian_date)),
+                _ => problems.push(format!(
+                    "day {} has {} contents",
+                    day.gregorian_date,
+                    dated.len()
+                )),
+            }
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("invalid daily_content.csv:\n{}", problems.join("\n"));
+        }
+        Ok(())
+    }
+
+    fn make_yearly_prayer_times(year: u16, days: Vec<(u8, DayInputDto)>) -> Vec<DailyPrayerTime> {
+        days.into_iter()
+            .zip(1..)
+            .map(|((month, day), index)| DailyPrayerTime {
+                gregorian_date: GregorianDate {
+                    index,
+                    day: day.day,
+                    month,
+                    year,
+                },
+                week_id: None,
+                hadith: None,
+                content: None,
+                display: None,
+                hijri_date: day.hijri,
+                prayer_times: domain::PrayerTimes {
+                    fajr: day.fajr,
+                    sunrise: day.sunrise,
+                    dhuhr: day.dhuhr,
+                    asr: day.asr,
+                    maghrib: day.maghrib,
+                    ishaa: day.ishaa,
+                },
+                events: vec![],
             })
             .collect()
     }
 
-    fn make_yearly_prayer_times(
-        year: u16,
-        input_dir_map: HashMap<String, PathBuf>,
-        year_events: HashMap<String, Event>,
-    ) -> Fallible<Vec<DailyPrayerTime>> {
-        let mut all_year = vec![];
-        let mut days_count: u16 = 1;
+    pub fn generate_daily_prayer_times(&self) -> Fallible<()> {
+        for (day, day_idx) in self.yearly_prayer_times.iter().zip(self.render_days()) {
+            let date = &day.gregorian_date;
+            let day_path = pathbuf![
+                "day",
+                date.year.to_string(),
+                format!("{:02}", date.month),
+                format!("{:02}", date.day)
+            ];
+            self.write_output(day_path, &day_idx)?;
+        }
+        Ok(())
+    }
 
-        // loop over the 12 months
-        for i in 1..=12 {
-            if let Some(month_file) = input_dir_map.get(&format!("{i:02}.csv")) {
-                let days = csv::Reader::from_path(month_file)?
-                    .deserialize()
-                    .flatten()
-                    .collect::<Vec<DayInputDto>>();
-                for day in days {
-                    let event_key = format!("{}/{}", day.day, i);
-                    let event = year_events.get(&event_key);
-                    let daily_prayer_time = DailyPrayerTime {
-                        gregorian_date: GregorianDate {
-                            index: days_count,
-                            day: day.day,
-                            month: i,
-                            year,
-                        },
-                        week_id: None,
-                        hijri_date: day.hijri,
-                        prayer_times: domain::PrayerTimes {
-                            fajr: day.fajr,
-                            sunrise: day.sunrise,
-                            dhuhr: day.dhuhr,
-                            asr: day.asr,
-                            maghrib: day.maghrib,
-                            ishaa: day.ishaa,
-                        },
-                        event: event.cloned(),
-                    };
-                    all_year.push(daily_prayer_time);
-                    days_count += 1;
+    /// Rewrites the 12-hour prayer times of the input as `HH:MM` ones
+    pub fn resolve_12_hour_times(&mut self) -> Fallible<()> {
+        for day in &mut self.yearly_prayer_times {
+            day.prayer_times.resolve_12_hour().with_context(|| {
+                format!("invalid 12-hour prayer times on {}", day.gregorian_date)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Moves the prayer times of every day by the minutes of `adjustments`
+    pub fn adjust_times(&mut self, adjustments: &[Adjustment]) -> Fallible<()> {
+        for day in &mut self.yearly_prayer_times {
+            for adjustment in adjustments {
+                day.prayer_times.adjust(*adjustment).with_context(|| {
+                    format!(
+                        "cannot adjust the {} time of {}",
+                        adjustment.prayer.as_str(),
+                        day.gregorian_date
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives every day a display block with its dates and prayer times written with `numerals`
+    /// and `time_format`, which is left out when they would be the same as the canonical values
+    pub fn make_display(&mut self, numerals: Numerals, time_format: TimeFormat) {
+        if numerals == Numerals::Latin && time_format == TimeFormat::H24 {
+            return;
+        }
+        for day in &mut self.yearly_prayer_times {
+            let dto = DayOutputDto::from(day.clone());
+            let times = dto
+                .prayer_times
+                .named()
+                .map(|(_, time)| numerals.render(&time_format.render(time)));
+            let [fajr, sunrise, dhuhr, asr, maghrib, ishaa] = times;
+            day.display = Some(DayDisplay {
+                gregorian: numerals.render(&dto.gregorian),
+                hijri: numerals.render(&dto.hijri),
+                prayer_times: domain::PrayerTimes {
+                    fajr,
+                    sunrise,
+                    dhuhr,
+                    asr,
+                    maghrib,
+                    ishaa,
+                },
+            });
+        }
+    }
+
+    /// Lays out the weeks of the year, giving every day its week id and the hadith of its week
+    ///
+    /// Has to run before any index is generated, as they all embed the week ids.
+    pub fn make_weeks(&mut self, starting_at: WeekDay, scheme: WeekScheme) -> Fallible<()> {
+        let Some(day_one) = self.yearly_prayer_times.first() else {
+            return Ok(());
+        };
+        let year_num = day_one.gregorian_date.year;
+        let mut weeks = match scheme {
+            WeekScheme::Chunked => self.make_chunked_weeks(year_num, starting_at)?,
+            WeekScheme::Iso => self.make_iso_weeks(),
+            WeekScheme::Contained => self.make_contained_weeks(year_num, starting_at)?,
+        };
+        self.assign_hadith(&mut weeks)?;
+        for week in &weeks {
+            for day in &mut self.yearly_prayer_times[week.days.clone()] {
+                day.week_id = Some(week.id);
+                day.hadith = week.hadith.clone();
+            }
+        }
+        self.weeks = weeks;
+        Ok(())
+    }
+
+    /// Splits the year into 53 weeks, each one ending on the day before `starting_at`
+    fn make_chunked_weeks(&self, year_num: u16, starting_at: WeekDay) -> Fallible<Vec<Week>> {
+        let last_day_of_week = chrono::Weekday::from(&starting_at.previous());
+        let mut weeks = vec![];
+        let mut next_day = 0;
+        for number in 1..=53 {
+            let first_day = next_day;
+            while next_day < self.yearly_prayer_times.len() && next_day - first_day < 7 {
+                let weekday = self.yearly_prayer_times[next_day].gregorian_date.weekday();
+                next_day += 1;
+                if weekday == Some(last_day_of_week) {
+                    break;
                 }
             }
+            let days = &self.yearly_prayer_times[first_day..next_day];
+            weeks.push(Week {
+                id: format!("{year_num}{number:02}").parse()?,
+                number,
+                days: first_day..next_day,
+                start: days.first().and_then(|d| d.gregorian_date.to_naive_date()),
+                end: days.last().and_then(|d| d.gregorian_date.to_naive_date()),
+                hadith: None,
+            });
         }
+        Ok(weeks)
+    }
 
-        all_year.sort();
+    /// Groups the days into ISO-8601 weeks, identified by their ISO year and week number
+    fn make_iso_weeks(&self) -> Vec<Week> {
+        let mut weeks: Vec<Week> = vec![];
+        for (idx, day) in self.yearly_prayer_times.iter().enumerate() {
+            let Some(date) = day.gregorian_date.to_naive_date() else {
+                continue;
+            };
+            let iso_week = date.iso_week();
+            let id = u64::from(iso_week.year() as u16) * 100 + u64::from(iso_week.week());
+            match weeks.last_mut() {
+                Some(week) if week.id == id => week.days.end = idx + 1,
+                _ => {
+                    let start = date - Days::new(date.weekday().num_days_from_monday().into());
+                    weeks.push(Week {
+                        id,
+                        number: iso_week.week() as u16,
+                        days: idx..idx + 1,
+                        start: Some(start),
+                        end: Some(start + Days::new(6)),
+                        hadith: None,
+                    });
+                }
+            }
+        }
+        weeks
+    }
 
-        Ok(all_year)
+    /// Makes the weeks that start on `starting_at` and end within the year
+    fn make_contained_weeks(&self, year_num: u16, starting_at: WeekDay) -> Fallible<Vec<Week>> {
+        let first_day_of_week = chrono::Weekday::from(&starting_at);
+        let days = &self.yearly_prayer_times;
+        let Some(first_day) = days
+            .iter()
+            .position(|day| day.gregorian_date.weekday() == Some(first_day_of_week))
+        else {
+            return Ok(vec![]);
+        };
+        let mut weeks = vec![];
+        for (number, start) in (first_day..days.len()).step_by(7).enumerate() {
+            if start + 7 > days.len() {
+                break;
+            }
+            let number = number as u16 + 1;
+            weeks.push(Week {
+                id: format!("{year_num}{number:02}").parse()?,
+                number,
+                days: start..start + 7,
+                start: days[start].gregorian_date.to_naive_date(),
+                end: days[start + 6].gregorian_date.to_naive_date(),
+                hadith: None,
+            });
+        }
+        Ok(weeks)
     }
 
-    pub fn generate_daily_prayer_times(&self) -> Fallible<()> {
-        for i in 1..=12 {
-            let days = self
-                .yearly_prayer_times
-                .clone()
-                .into_iter()
-                .filter(|day| day.gregorian_date.month == i)
+    /// Gives every week its hadith, making sure each week has exactly one when there are any
+    fn assign_hadith(&self, weeks: &mut [Week]) -> Fallible<()> {
+        if self.weekly_hadith.is_empty() {
+            return Ok(());
+        }
+        let mut problems = vec![];
+        // The weeks every hadith starts in, to report the ones in none or in several of them
+        let mut starts = vec![vec![]; self.weekly_hadith.len()];
+        for week in weeks {
+            let days = &self.yearly_prayer_times[week.days.clone()];
+            if days.is_empty() {
+                continue;
+            }
+            let hadiths = self
+                .weekly_hadith
+                .iter()
+                .zip(&mut starts)
+                .filter(|(h, _)| h.starts_in(week.number, days))
+                .map(|(h, starts)| {
+                    starts.push(week.id.to_string());
+                    h
+                })
                 .collect::<Vec<_>>();
-            self.generate_day_idx(days)?;
+            match hadiths.as_slice() {
+                [hadith] => week.hadith = Some(hadith.hadith.clone()),
+                [] => problems.push(format!("week {} has no hadith", week.id)),
+                _ => problems.push(format!("week {} has {} hadiths", week.id, hadiths.len())),
+            }
+        }
+        for (hadith, starts) in self.weekly_hadith.iter().zip(starts) {
+            match starts.as_slice() {
+                [_] => {}
+                [] => problems.push(format!(
+                    "hadith {:?} starts {}, which is in no week",
+                    hadith.hadith.hadith, hadith.start
+                )),
+                // e.g. a week number with ISO weeks, which number both ends of the year 1
+                _ => problems.push(format!(
+                    "hadith {:?} starts {}, which is in weeks {}",
+                    hadith.hadith.hadith,
+                    hadith.start,
+                    starts.join(", ")
+                )),
+            }
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("invalid weekly_hadith.csv:\n{}", problems.join("\n"));
         }
         Ok(())
     }
 
-    pub fn generate_weekly_prayer_times(&mut self, starting_at: WeekDay) -> Fallible<()> {
-        self.generate_week_idx(self.yearly_prayer_times.clone(), starting_at)
+    pub fn generate_weekly_prayer_times(&self) -> Fallible<()> {
+        let Some(year_num) = self.year_num() else {
+            return Ok(());
+        };
+        let week_path = pathbuf!["year", "weeks", year_num.to_string()];
+        self.write_output(week_path, &self.render_week_idx()?)
     }
 
     pub fn generate_monthly_prayer_times(&self) -> Fallible<()> {
-        self.generate_monthly_idx(self.yearly_prayer_times.clone())
+        let Some(year_num) = self.year_num() else {
+            return Ok(());
+        };
+        let month_dir = pathbuf!["month", year_num.to_string()];
+        for i in 1..=12 {
+            let month_path = pathbuf![month_dir.clone(), format!("{i:02}")];
+            self.write_output(month_path, &self.render_month_idx(i))?;
+        }
+        Ok(())
     }
 
     pub fn generate_yearly_prayer_times(&self) -> Fallible<()> {
-        self.generate_year_idx(self.yearly_prayer_times.clone())
+        let Some(year_num) = self.year_num() else {
+            return Ok(());
+        };
+        let compact_path = pathbuf!["year", "compact", format!("{year_num}.bin")];
+        if self.compact_year {
+            self.write_file(compact_path, &self.render_compact_year())?;
+        } else {
+            // Left over from a previous run that had it enabled
+            self.remove_file(&compact_path)?;
+        }
+        let year_path = pathbuf!["year", "days", year_num.to_string()];
+        self.write_output(year_path, &self.render_year_idx()?)
+    }
+
+    /// Writes the hijri month and year indexes of every hijri month the year touches
+    pub fn generate_hijri_prayer_times(&self) -> Fallible<()> {
+        let (hijri_months, hijri_years) = self.render_hijri_idx(self.load_neighbour_days()?);
+        for ((hijri_year, month), days) in hijri_months {
+            let month_path = pathbuf![
+                "hijri",
+                "month",
+                hijri_year.to_string(),
+                format!("{month:02}")
+            ];
+            self.write_output(month_path, &days)?;
+        }
+        for (hijri_year, days) in hijri_years {
+            let year_path = pathbuf!["hijri", "year", hijri_year.to_string()];
+            self.write_output(year_path, &days)?;
+        }
+        Ok(())
     }
 
-    fn generate_day_idx(&self, days_of_month: Vec<DailyPrayerTime>) -> Fallible<()> {
-        let Some(day_one) = days_of_month.first() else {
+    /// Writes the imsakiyah of every Ramadan the year touches
+    pub fn generate_ramadan_prayer_times(&self) -> Fallible<()> {
+        let neighbours = self.load_neighbour_days()?;
+        for ramadan in self.render_ramadan_idx(neighbours) {
+            let ramadan_path = pathbuf!["ramadan", ramadan.hijri_year.to_string()];
+            self.write_output(ramadan_path, &ramadan)?;
+        }
+        Ok(())
+    }
+
+    pub fn generate_events(&self) -> Fallible<()> {
+        let Some(year_num) = self.year_num() else {
             return Ok(());
         };
-        let year_num = day_one.gregorian_date.year;
-        let month_num = day_one.gregorian_date.month;
-        let dir = pathbuf![
-            self.output_dir.clone(),
-            "day",
-            year_num.to_string(),
-            format!("{month_num:02}")
-        ];
-        fs::create_dir_all(&dir)?;
-        for day in days_of_month {
-            let day_path = pathbuf![dir.clone(), format!("{:02}.json", day.gregorian_date.day)];
-            let day_file = File::create(day_path)?;
-            let day_idx: DayOutputDto = day.into();
-            let json = serde_json::to_value(&day_idx)?;
-            serde_json::to_writer_pretty(day_file, &json)?;
+        let events_path = pathbuf!["events", year_num.to_string()];
+        self.write_output(events_path, &self.render_events_idx())
+    }
+
+    pub fn generate_labels(&self) -> Fallible<()> {
+        for locale in &self.locales {
+            let path = pathbuf!["labels", locale.code()];
+            self.write_output(path, &locale.labels())?;
         }
         Ok(())
     }
 
-    fn generate_week_idx(
-        &mut self,
-        days_of_month: Vec<DailyPrayerTime>,
-        starting_at: WeekDay,
-    ) -> Fallible<()> {
-        let Some(day_one) = days_of_month.first() else {
+    pub fn generate_sha1(&self) -> Fallible<()> {
+        let Some(year_num) = self.year_num() else {
             return Ok(());
         };
-        let year_num = day_one.gregorian_date.year;
-        let week_dir = pathbuf![self.output_dir.clone(), "year", "weeks"];
-        fs::create_dir_all(&week_dir)?;
-        let week_path = pathbuf![week_dir.clone(), format!("{year_num}.json")];
-        let week_file = File::create(week_path)?;
-        let mut days_iter = days_of_month.into_iter().enumerate();
-        let mut year_weeks = YearWeeksOutputDto {
-            weeks: vec![],
-            sha1: self.make_sha1()?,
+        let sha1_path = pathbuf!["sha1", year_num.to_string()];
+        let sha1 = json!({
+            "sha1": self.render_sha1()?,
+        });
+        self.write_output(sha1_path, &sha1)
+    }
+
+    /// Writes the whole year as a SQLite database, after the weeks have been generated
+    pub fn generate_sqlite(&self) -> Fallible<()> {
+        let Some(year_num) = self.year_num() else {
+            return Ok(());
         };
+        let sqlite_path = pathbuf!["sqlite", format!("{year_num}.sqlite")];
+        self.write_file(sqlite_path, &self.render_sqlite()?)
+    }
+
+    /// The gregorian year of the days, `None` when there are no days
+    pub fn year_num(&self) -> Option<u16> {
+        self.yearly_prayer_times
+            .first()
+            .map(|day| day.gregorian_date.year)
+    }
+
+    /// The day index of every day of the year
+    pub fn render_days(&self) -> Vec<DayOutputDto> {
+        self.yearly_prayer_times
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .collect()
+    }
 
-        for week_idx in 1..=53 {
-            let id = format!("{year_num}{:02}", week_idx).parse()?;
-            let hadith = self.weekly_hadith.get(&week_idx);
-            let mut week = WeekOutputDto {
-                id,
+    /// The week index of the year, after [`Generator::make_weeks`]
+    pub fn render_week_idx(&self) -> Fallible<YearWeeksOutputDto> {
+        let mut weeks = vec![];
+        for week in &self.weeks {
+            let mut week_dto = WeekOutputDto {
+                id: week.id,
+                start: week.start.map(|date| date.format("%d/%m/%Y").to_string()),
+                end: week.end.map(|date| date.format("%d/%m/%Y").to_string()),
                 mon: None,
                 tue: None,
                 wed: None,
@@ -213,148 +739,461 @@ impl Generator {
                 fri: None,
                 sat: None,
                 sun: None,
-                hadith: hadith.map(|h| HaidthOutputDto {
-                    hadith: h.hadith.clone(),
-                    note: h.note.clone(),
-                }),
+                hadith: week.hadith.clone().map(Into::into),
             };
-            for _ in 0..7 {
-                let Some((idx, day)) = days_iter.next() else {
-                    break;
-                };
-                self.yearly_prayer_times[idx].week_id = Some(id);
-                let LocalResult::Single(day_of_the_week) = Utc.with_ymd_and_hms(
-                    day.gregorian_date.year.into(),
-                    day.gregorian_date.month.into(),
-                    day.gregorian_date.day.into(),
-                    0,
-                    0,
-                    0,
-                ) else {
-                    break;
+            for day in self.yearly_prayer_times[week.days.clone()].iter().cloned() {
+                let slot = match day.gregorian_date.weekday() {
+                    Some(chrono::Weekday::Mon) => &mut week_dto.mon,
+                    Some(chrono::Weekday::Tue) => &mut week_dto.tue,
+                    Some(chrono::Weekday::Wed) => &mut week_dto.wed,
+                    Some(chrono::Weekday::Thu) => &mut week_dto.thu,
+                    Some(chrono::Weekday::Fri) => &mut week_dto.fri,
+                    Some(chrono::Weekday::Sat) => &mut week_dto.sat,
+                    Some(chrono::Weekday::Sun) => &mut week_dto.sun,
+                    None => continue,
                 };
-                let day_of_the_week = day_of_the_week.weekday();
-                match day_of_the_week {
-                    chrono::Weekday::Mon => {
-                        week.mon = Some(day.into());
-                        if starting_at.previous() == WeekDay::Mon {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Tue => {
-                        week.tue = Some(day.into());
-                        if starting_at.previous() == WeekDay::Tue {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Wed => {
-                        week.wed = Some(day.into());
-                        if starting_at.previous() == WeekDay::Wed {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Thu => {
-                        week.thu = Some(day.into());
-                        if starting_at.previous() == WeekDay::Thu {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Fri => {
-                        week.fri = Some(day.into());
-                        if starting_at.previous() == WeekDay::Fri {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Sat => {
-                        week.sat = Some(day.into());
-                        if starting_at.previous() == WeekDay::Sat {
-                            break;
-                        }
-                    }
-                    chrono::Weekday::Sun => {
-                        week.sun = Some(day.into());
-                        if starting_at.previous() == WeekDay::Sun {
-                            break;
-                        }
-                    }
+                *slot = Some(day.into());
+            }
+            weeks.push(week_dto);
+        }
+        Ok(YearWeeksOutputDto {
+            weeks,
+            sha1: self.render_sha1()?,
+        })
+    }
+
+    /// The month index of the `month`th month of the year
+    pub fn render_month_idx(&self, month: u8) -> Vec<DayOutputDto> {
+        self.yearly_prayer_times
+            .iter()
+            .filter(|day| day.gregorian_date.month == month)
+            .cloned()
+            .map(Into::into)
+            .collect()
+    }
+
+    pub fn render_year_idx(&self) -> Fallible<YearOutputDto> {
+        Ok(YearOutputDto {
+            year: self.render_days(),
+            sha1: self.render_sha1()?,
+        })
+    }
+
+    /// The year as a fixed-width binary table, see [`compact::encode_year`]
+    pub fn render_compact_year(&self) -> Vec<u8> {
+        compact::encode_year(
+            self.year_num().unwrap_or_default(),
+            &self.yearly_prayer_times,
+        )
+    }
+
+    /// Every event of the year, with multi-day events listed once with their first and last
+    /// days
+    pub fn render_events_idx(&self) -> EventsOutputDto {
+        let mut distinct_events = vec![];
+        for day in &self.yearly_prayer_times {
+            for event in &day.events {
+                if !distinct_events.contains(&event) {
+                    distinct_events.push(event);
                 }
             }
-            year_weeks.weeks.push(week);
         }
-        let json = serde_json::to_value(&year_weeks)?;
-        serde_json::to_writer_pretty(week_file, &json)?;
-        Ok(())
+        let mut events = vec![];
+        for event in distinct_events {
+            let mut occurrence: Option<(DayOutputDto, DayOutputDto)> = None;
+            let mut last_index = None;
+            for day in &self.yearly_prayer_times {
+                if !day.events.contains(event) {
+                    continue;
+                }
+                let index = day.gregorian_date.index;
+                let day: DayOutputDto = day.clone().into();
+                occurrence = match occurrence {
+                    // Consecutive days are the same occurrence of the event
+                    Some((first, _)) if last_index == Some(index - 1) => Some((first, day)),
+                    Some((first, last)) => {
+                        events.push(EventsEntryOutputDto::new(event, first, last));
+                        Some((day.clone(), day))
+                    }
+                    None => Some((day.clone(), day)),
+                };
+                last_index = Some(index);
+            }
+            if let Some((first, last)) = occurrence {
+                events.push(EventsEntryOutputDto::new(event, first, last));
+            }
+        }
+        events.sort_by_key(|event| event.start_id);
+        EventsOutputDto { events }
     }
 
-    fn generate_monthly_idx(&self, year: Vec<DailyPrayerTime>) -> Fallible<()> {
-        let Some(day_one) = year.first() else {
-            return Ok(());
+    /// The hijri month and year indexes of every hijri month the year touches
+    ///
+    /// Hijri months and years span two gregorian years, so they are completed with the
+    /// `neighbours` days of the previous and next gregorian years
+    pub fn render_hijri_idx(&self, neighbours: Vec<DayOutputDto>) -> (HijriMonths, HijriYears) {
+        let (hijri_months, touched_months) = self.make_hijri_months(neighbours);
+        let mut months = HijriMonths::new();
+        let mut years = HijriYears::new();
+        for ((hijri_year, month), days) in hijri_months {
+            let touched_year = touched_months.iter().any(|(year, _)| *year == hijri_year);
+            if !touched_year {
+                continue;
+            }
+            years.entry(hijri_year).or_default().extend(days.clone());
+            if touched_months.contains(&(hijri_year, month)) {
+                months.insert((hijri_year, month), days);
+            }
+        }
+        (months, years)
+    }
+
+    /// The imsakiyah of every Ramadan the year touches, completed with the `neighbours` days
+    pub fn render_ramadan_idx(&self, neighbours: Vec<DayOutputDto>) -> Vec<RamadanOutputDto> {
+        let (hijri_months, touched_months) = self.make_hijri_months(neighbours);
+        hijri_months
+            .into_iter()
+            .filter(|(key, _)| key.1 == RAMADAN && touched_months.contains(key))
+            .map(|((hijri_year, _), days)| RamadanOutputDto {
+                hijri_year,
+                days: days
+                    .into_iter()
+                    .map(|day| RamadanDayOutputDto::new(day, self.imsak_offset))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// The whole year as a SQLite database, after [`Generator::make_weeks`]
+    pub fn render_sqlite(&self) -> Fallible<Vec<u8>> {
+        sqlite::export_year(&self.yearly_prayer_times)
+    }
+
+    /// The days of the previous and next years, taken from their yearly indexes when they were
+    /// already generated in the output
+    pub fn load_neighbour_days(&self) -> Fallible<Vec<DayOutputDto>> {
+        let Some(year_num) = self.year_num() else {
+            return Ok(vec![]);
         };
-        let year_num = day_one.gregorian_date.year;
-        let month_dir = pathbuf![self.output_dir.clone(), "month", year_num.to_string()];
-        fs::create_dir_all(&month_dir)?;
-        let days_iter = year.into_iter();
-        for i in 1..=12 {
-            let month = days_iter
-                .clone()
-                .filter(|day| day.gregorian_date.month == i)
-                .map(|day| day.into())
-                .collect::<Vec<DayOutputDto>>();
-            let month_path = pathbuf![month_dir.clone(), format!("{i:02}.json")];
-            let month_file = File::create(month_path)?;
-            let json = serde_json::to_value(&month)?;
-            serde_json::to_writer_pretty(month_file, &json)?;
+        let mut days = vec![];
+        for neighbour in [year_num - 1, year_num + 1] {
+            let neighbour_path = pathbuf!["year", "days", format!("{neighbour}.json")];
+            let Some(bytes) = self.sink.read(&neighbour_path)? else {
+                continue;
+            };
+            let neighbour_year: YearOutputDto = serde_json::from_slice(&bytes)?;
+            days.extend(neighbour_year.year);
+        }
+        Ok(days)
+    }
+
+    /// Groups the days of the year and `neighbours` by hijri month, along with the months that
+    /// have days in this year
+    fn make_hijri_months(
+        &self,
+        neighbours: Vec<DayOutputDto>,
+    ) -> (HijriMonths, BTreeSet<(u16, u8)>) {
+        let mut hijri_months = HijriMonths::new();
+        let mut touched_months = BTreeSet::new();
+        let mut days = neighbours
+            .into_iter()
+            .map(|day| (day.id, day))
+            .collect::<BTreeMap<_, _>>();
+        for day in self.yearly_prayer_times.clone() {
+            if let Some(hijri) = day.hijri() {
+                touched_months.insert((hijri.year, hijri.month));
+            }
+            let day: DayOutputDto = day.into();
+            days.insert(day.id, day);
+        }
+
+        for day in days.into_values() {
+            if let Some(hijri) = HijriDate::parse(&day.hijri) {
+                hijri_months
+                    .entry((hijri.year, hijri.month))
+                    .or_default()
+                    .push(day);
+            }
+        }
+        (hijri_months, touched_months)
+    }
+
+    /// The sha1 published with the yearly indexes, see [`Generator::hash_days`]
+    pub fn render_sha1(&self) -> Fallible<String> {
+        Self::hash_days(&self.render_days())
+    }
+
+    /// Computes the sha1 published with the yearly indexes out of the days of the year
+    ///
+    /// The hash is always taken over the compact json of the days, so it does not depend on
+    /// whether the files are minified or compressed
+    pub fn hash_days(days: &[DayOutputDto]) -> Fallible<String> {
+        let mut hasher = Sha1::new();
+        let json = serde_json::to_string(days)?;
+        hasher.update(json);
+        let result = hasher.finalize();
+        Ok(format!("{:x}", result))
+    }
+
+    /// Writes `value` once per output format, at `path` with the extension of the format, and
+    /// removes the files of the other formats that a previous run may have written
+    fn write_output<T: Serialize>(&self, path: PathBuf, value: &T) -> Fallible<()> {
+        for format in OutputFormat::ALL {
+            let format_path = path.with_extension(format.extension());
+            if self.formats.contains(&format) {
+                let bytes = format.encode(value, self.minify)?;
+                self.write_file(format_path, &bytes)?;
+            } else {
+                self.remove_file(&format_path)?;
+            }
         }
         Ok(())
     }
 
-    fn generate_year_idx(&self, year: Vec<DailyPrayerTime>) -> Fallible<()> {
-        let Some(day_one) = year.first() else {
-            return Ok(());
-        };
-        let year_num = day_one.gregorian_date.year;
-        let year_dir = pathbuf![self.output_dir.clone(), "year", "days"];
-        fs::create_dir_all(&year_dir)?;
-
-        let year_path = pathbuf![year_dir.clone(), format!("{year_num}.json")];
-        let year_file = File::create(year_path)?;
-        let days: Vec<DayOutputDto> = year.into_iter().map(Into::into).collect();
-        let year = YearOutputDto {
-            year: days,
-            sha1: self.make_sha1()?,
-        };
-        let json = serde_json::to_value(&year)?;
-        serde_json::to_writer_pretty(year_file, &json)?;
+    /// Removes the file at `path` along with its sidecars
+    fn remove_file(&self, path: &Path) -> Fallible<()> {
+        self.sink.remove(path)?;
+        for compression in Compression::ALL {
+            self.sink.remove(&Self::sidecar_path(path, compression))?;
+        }
         Ok(())
     }
 
-    pub fn generate_sha1(&self) -> Fallible<()> {
-        let Some(day_one) = self.yearly_prayer_times.first() else {
-            return Ok(());
-        };
-        let year_num = day_one.gregorian_date.year;
-        let sha1_dir = pathbuf![self.output_dir.clone(), "sha1"];
-        fs::create_dir_all(&sha1_dir)?;
-        let sha1_path = pathbuf![sha1_dir, format!("{year_num}.json")];
-        let sha1 = json!({
-            "sha1": self.make_sha1()?,
-        });
-        let sha1_file = File::create(sha1_path)?;
-        serde_json::to_writer_pretty(sha1_file, &sha1)?;
+    fn sidecar_path(path: &Path, compression: Compression) -> PathBuf {
+        let mut sidecar_path = path.as_os_str().to_owned();
+        sidecar_path.push(format!(".{}", compression.extension()));
+        PathBuf::from(sidecar_path)
+    }
+
+    fn write_file(&self, path: PathBuf, bytes: &[u8]) -> Fallible<()> {
+        self.sink.write(&path, bytes)?;
+        for compression in Compression::ALL {
+            let sidecar_path = Self::sidecar_path(&path, compression);
+            if self.compressions.contains(&compression) {
+                self.sink
+                    .write(&sidecar_path, &compression.compress(bytes)?)?;
+            } else {
+                // Left over from a previous run that had this compression enabled
+                self.sink.remove(&sidecar_path)?;
+            }
+        }
         Ok(())
     }
+}
 
-    fn make_sha1(&self) -> Fallible<String> {
-        let mut hasher = Sha1::new();
-        let yearly_prayer_times: Vec<DayOutputDto> = self
+/// Reads every row of the CSV file at `path`, failing on the first one that does not fit in `T`
+fn read_rows<T: DeserializeOwned>(path: &Path) -> Fallible<Vec<T>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    reader
+        .deserialize()
+        .map(|row| row.with_context(|| format!("invalid row in {}", path.display())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every day of 2025 with the same prayer times
+    fn year_2025() -> Vec<DailyPrayerTime> {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .iter_days()
+            .take_while(|date| date.year() == 2025)
+            .zip(1..)
+            .map(|(date, index)| DailyPrayerTime {
+                gregorian_date: GregorianDate {
+                    index,
+                    day: date.day() as u16,
+                    month: date.month() as u8,
+                    year: 2025,
+                },
+                hijri_date: "1/7/1446".to_string(),
+                prayer_times: domain::PrayerTimes {
+                    fajr: "04:33".to_string(),
+                    sunrise: "06:00".to_string(),
+                    dhuhr: "12:00".to_string(),
+                    asr: "15:00".to_string(),
+                    maghrib: "18:00".to_string(),
+                    ishaa: "19:30".to_string(),
+                },
+                week_id: None,
+                hadith: None,
+                content: None,
+                display: None,
+                events: vec![],
+            })
+            .collect()
+    }
+
+    /// Lays out the weeks of 2025 and writes the week index to a [`MemorySink`]
+    fn render_weeks(starting_at: WeekDay, scheme: WeekScheme) -> (Generator, YearWeeksOutputDto) {
+        let sink = MemorySink::default();
+        let mut generator = Generator::from_days(year_2025());
+        generator.sink = Box::new(sink.clone());
+        generator.make_weeks(starting_at, scheme).unwrap();
+        generator.generate_weekly_prayer_times().unwrap();
+        let files = sink.files();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            [Path::new("year/weeks/2025.json")]
+        );
+        let weeks = serde_json::from_slice(&files[Path::new("year/weeks/2025.json")]).unwrap();
+        (generator, weeks)
+    }
+
+    fn hadith(start: HadithStart, text: &str) -> WeeklyHadith {
+        WeeklyHadith {
+            start,
+            hadith: Hadith {
+                hadith: text.to_string(),
+                note: None,
+            },
+        }
+    }
+
+    #[test]
+    fn chunked_weeks_end_on_the_day_before_the_start_day() {
+        let (generator, index) = render_weeks(WeekDay::Sat, WeekScheme::Chunked);
+        assert_eq!(index.weeks.len(), 53);
+        // 2025 starts on a Wednesday, so the first week is cut short on Friday
+        let first = &index.weeks[0];
+        assert_eq!(first.id, 202501);
+        assert_eq!(first.start.as_deref(), Some("01/01/2025"));
+        assert_eq!(first.end.as_deref(), Some("03/01/2025"));
+        assert!(first.sat.is_none());
+        assert_eq!(first.fri.as_ref().map(|day| day.id), Some(20250103));
+        let last = &index.weeks[52];
+        assert_eq!(last.id, 202553);
+        assert_eq!(last.start.as_deref(), Some("27/12/2025"));
+        assert_eq!(last.end.as_deref(), Some("31/12/2025"));
+        assert!(generator
             .yearly_prayer_times
-            .clone()
-            .into_iter()
-            .map(|day| day.into())
+            .iter()
+            .all(|day| day.week_id.is_some()));
+    }
+
+    #[test]
+    fn iso_weeks_are_numbered_within_the_iso_year() {
+        let (generator, index) = render_weeks(WeekDay::Sat, WeekScheme::Iso);
+        assert_eq!(index.weeks.len(), 53);
+        let first = &index.weeks[0];
+        assert_eq!(first.id, 202501);
+        assert_eq!(first.start.as_deref(), Some("30/12/2024"));
+        assert_eq!(first.end.as_deref(), Some("05/01/2025"));
+        assert!(first.mon.is_none());
+        assert_eq!(first.wed.as_ref().map(|day| day.id), Some(20250101));
+        // The last days of 2025 are in the first week of the ISO year 2026
+        let last = &index.weeks[52];
+        assert_eq!(last.id, 202601);
+        assert_eq!(last.start.as_deref(), Some("29/12/2025"));
+        assert_eq!(last.end.as_deref(), Some("04/01/2026"));
+        assert_eq!(
+            generator.yearly_prayer_times.last().unwrap().week_id,
+            Some(202601)
+        );
+    }
+
+    #[test]
+    fn contained_weeks_leave_the_partial_weeks_out() {
+        let (generator, index) = render_weeks(WeekDay::Sat, WeekScheme::Contained);
+        assert_eq!(index.weeks.len(), 51);
+        let first = &index.weeks[0];
+        assert_eq!(first.id, 202501);
+        assert_eq!(first.start.as_deref(), Some("04/01/2025"));
+        assert_eq!(first.end.as_deref(), Some("10/01/2025"));
+        let last = &index.weeks[50];
+        assert_eq!(last.id, 202551);
+        assert_eq!(last.start.as_deref(), Some("20/12/2025"));
+        assert_eq!(last.end.as_deref(), Some("26/12/2025"));
+        let days = &generator.yearly_prayer_times;
+        assert_eq!(days[2].week_id, None);
+        assert_eq!(days[3].week_id, Some(202501));
+        assert_eq!(days[359].week_id, Some(202551));
+        assert_eq!(days[360].week_id, None);
+    }
+
+    #[test]
+    fn files_of_dropped_formats_are_removed() {
+        let sink = MemorySink::default();
+        let mut generator = Generator::from_days(year_2025());
+        generator.sink = Box::new(sink.clone());
+        generator.formats = vec![OutputFormat::Cbor, OutputFormat::Json];
+        generator.compressions = vec![Compression::Gzip];
+        generator.compact_year = true;
+        generator.generate_yearly_prayer_times().unwrap();
+        let written = sink.files().into_keys().collect::<Vec<_>>();
+        assert_eq!(
+            written,
+            [
+                "year/compact/2025.bin",
+                "year/compact/2025.bin.gz",
+                "year/days/2025.cbor",
+                "year/days/2025.cbor.gz",
+                "year/days/2025.json",
+                "year/days/2025.json.gz",
+            ]
+            .map(PathBuf::from)
+        );
+
+        generator.formats = vec![OutputFormat::Json];
+        generator.compressions = vec![];
+        generator.compact_year = false;
+        generator.generate_yearly_prayer_times().unwrap();
+        let written = sink.files().into_keys().collect::<Vec<_>>();
+        assert_eq!(written, [PathBuf::from("year/days/2025.json")]);
+    }
+
+    #[test]
+    fn hadiths_are_assigned_to_the_week_of_their_start() {
+        let mut generator = Generator::from_days(year_2025());
+        generator.weekly_hadith = (1..=53)
+            .map(|week| hadith(HadithStart::Week(week), &format!("hadith {week}")))
             .collect();
-        let json = serde_json::to_string(&yearly_prayer_times)?;
-        hasher.update(json);
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
+        generator.weekly_hadith[1].start =
+            HadithStart::Gregorian(NaiveDate::from_ymd_opt(2025, 1, 8).unwrap());
+        generator
+            .make_weeks(WeekDay::Sat, WeekScheme::Chunked)
+            .unwrap();
+        let hadith_of = |index: usize| {
+            generator.yearly_prayer_times[index]
+                .hadith
+                .as_ref()
+                .map(|h| h.hadith.clone())
+        };
+        assert_eq!(hadith_of(0).as_deref(), Some("hadith 1"));
+        assert_eq!(hadith_of(3).as_deref(), Some("hadith 2"));
+        assert_eq!(hadith_of(364).as_deref(), Some("hadith 53"));
+    }
+
+    #[test]
+    fn hadiths_outside_every_week_are_reported() {
+        let mut generator = Generator::from_days(year_2025());
+        let first_days = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        generator.weekly_hadith = vec![hadith(HadithStart::Gregorian(first_days), "early")];
+        generator.weekly_hadith.extend((0..51).map(|week| {
+            let start = NaiveDate::from_ymd_opt(2025, 1, 4).unwrap() + Days::new(week * 7);
+            hadith(HadithStart::Gregorian(start), "weekly")
+        }));
+        let e = generator
+            .make_weeks(WeekDay::Sat, WeekScheme::Contained)
+            .unwrap_err();
+        assert!(e.to_string().contains("\"early\" starts on 02/01/2025"));
+    }
+
+    #[test]
+    fn hadiths_starting_in_several_weeks_are_reported() {
+        let mut generator = Generator::from_days(year_2025());
+        generator.weekly_hadith = (1..=52)
+            .map(|week| hadith(HadithStart::Week(week), &format!("hadith {week}")))
+            .collect();
+        // The last days of 2025 are in the first ISO week of 2026
+        let e = generator
+            .make_weeks(WeekDay::Mon, WeekScheme::Iso)
+            .unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("\"hadith 1\" starts on week 1, which is in weeks 202501, 202601"));
     }
 }
@@ -0,0 +1,71 @@
+use super::generator::Generator;
+use crate::prelude::Fallible;
+
+/// The indexes a run can write
+///
+/// A new kind of index only has to be added here to be picked with `--index` and generated along
+/// with the other ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum IndexKind {
+    /// `year/weeks/<year>`, the weeks of the year and their hadith
+    Week,
+    /// `day/<year>/<month>/<day>`, one file per day
+    Day,
+    /// `year/days/<year>`, and `year/compact/<year>.bin` with `--compact-year`
+    Year,
+    /// `month/<year>/<month>`
+    Month,
+    /// `events/<year>`
+    Events,
+    /// `hijri/month/...` and `hijri/year/...` of every hijri month the year touches
+    Hijri,
+    /// `ramadan/<hijri year>`, the Ramadan timetable with imsak
+    Ramadan,
+    /// `labels/<locale>` of every `--locale`
+    Labels,
+    /// `sha1/<year>`
+    Sha1,
+    /// `sqlite/<year>.sqlite`
+    Sqlite,
+}
+
+impl IndexKind {
+    /// Every index, in the order they are generated
+    pub const ALL: [IndexKind; 10] = [
+        IndexKind::Week,
+        IndexKind::Day,
+        IndexKind::Year,
+        IndexKind::Month,
+        IndexKind::Events,
+        IndexKind::Hijri,
+        IndexKind::Ramadan,
+        IndexKind::Labels,
+        IndexKind::Sha1,
+        IndexKind::Sqlite,
+    ];
+
+    /// The indexes written when none are picked, the SQLite database being opt-in
+    pub fn defaults() -> Vec<IndexKind> {
+        Self::ALL
+            .into_iter()
+            .filter(|index| *index != IndexKind::Sqlite)
+            .collect()
+    }
+
+    /// Writes this index of the year to the sink of `generator`
+    pub fn generate(&self, generator: &Generator) -> Fallible<()> {
+        match self {
+            IndexKind::Week => generator.generate_weekly_prayer_times(),
+            IndexKind::Day => generator.generate_daily_prayer_times(),
+            IndexKind::Year => generator.generate_yearly_prayer_times(),
+            IndexKind::Month => generator.generate_monthly_prayer_times(),
+            IndexKind::Events => generator.generate_events(),
+            IndexKind::Hijri => generator.generate_hijri_prayer_times(),
+            IndexKind::Ramadan => generator.generate_ramadan_prayer_times(),
+            IndexKind::Labels => generator.generate_labels(),
+            IndexKind::Sha1 => generator.generate_sha1(),
+            IndexKind::Sqlite => generator.generate_sqlite(),
+        }
+    }
+}
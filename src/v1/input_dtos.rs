@@ -1,4 +1,11 @@
+use super::domain::Calendar;
+use super::domain::DailyContentKind;
 use super::domain::DailyPrayerTime;
+use super::domain::EventCategory;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use chrono::Datelike;
+use chrono::NaiveDate;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -13,20 +20,101 @@ pub struct DayInputDto {
     pub ishaa: String,
 }
 
+/// A day of a single file holding the whole year, either with a `date` column or with `month`
+/// and `day` columns
+#[derive(Debug, Deserialize)]
+pub struct YearlyDayInputDto {
+    /// `D/M/YYYY` or `YYYY-MM-DD` gregorian date of the day
+    pub date: Option<String>,
+    pub month: Option<u8>,
+    pub day: Option<u16>,
+    pub hijri: String,
+    pub fajr: String,
+    pub sunrise: String,
+    pub dhuhr: String,
+    pub asr: String,
+    pub maghrib: String,
+    pub ishaa: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct EventInputDto {
+    /// `D/M` day of the year the event falls on
     pub date: String,
+    /// `D/M` last day of a multi-day event
+    pub until: Option<String>,
+    /// The calendar of `date` and `until`, gregorian when left empty
+    pub calendar: Option<Calendar>,
+    pub category: Option<EventCategory>,
     pub ar: String,
     pub en: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WeeklyHadithInputDto {
-    pub week: u16,
+    /// The number of the week, superseded by `start` which does not depend on the week layout
+    pub week: Option<u16>,
+    /// `D/M/YYYY` date of a day of the week the hadith is for
+    pub start: Option<String>,
+    /// The calendar of `start`, gregorian when left empty
+    pub calendar: Option<Calendar>,
     pub hadith: String,
     pub note: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DailyContentInputDto {
+    /// `D/M/YYYY` date of the day the content is for
+    pub date: Option<String>,
+    /// The calendar of `date`, gregorian when left empty
+    pub calendar: Option<Calendar>,
+    /// The position of the content in the rotation, used when `date` is left empty
+    pub index: Option<usize>,
+    pub kind: Option<DailyContentKind>,
+    pub ar: String,
+    pub translation: Option<String>,
+    pub source: Option<String>,
+}
+
+/// A row of `columns.csv`, mapping a header of the input tables to the field it holds
+#[derive(Debug, Deserialize)]
+pub struct ColumnInputDto {
+    pub header: String,
+    /// The canonical name of the field, or `ignore` to leave the column out
+    pub field: String,
+}
+
+impl YearlyDayInputDto {
+    /// Splits the day into its month and the day as found in the monthly files, `None` when its
+    /// date is in another year than `year`
+    pub fn split(self, year: u16) -> Fallible<Option<(u8, DayInputDto)>> {
+        let (month, day) = match (&self.date, self.month, self.day) {
+            (Some(date), _, _) => {
+                let date = NaiveDate::parse_from_str(date.trim(), "%d/%m/%Y")
+                    .or_else(|_| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d"))
+                    .with_context(|| format!("invalid date {date:?}"))?;
+                if date.year() != i32::from(year) {
+                    return Ok(None);
+                }
+                (date.month() as u8, date.day() as u16)
+            }
+            (None, Some(month), Some(day)) => (month, day),
+            (None, _, _) => anyhow::bail!("day {:?} has no date, nor month and day", self.hijri),
+        };
+        let day = DayInputDto {
+            day,
+            hijri: self.hijri,
+            fajr: self.fajr,
+            sunrise: self.sunrise,
+            dhuhr: self.dhuhr,
+            asr: self.asr,
+            maghrib: self.maghrib,
+            ishaa: self.ishaa,
+        };
+        Ok(Some((month, day)))
+    }
+}
+
 impl From<DailyPrayerTime> for DayInputDto {
     fn from(day: DailyPrayerTime) -> Self {
         Self {
@@ -0,0 +1,707 @@
+use super::input_dtos::ColumnInputDto;
+use super::input_dtos::DayInputDto;
+use super::input_dtos::YearlyDayInputDto;
+use super::localization::Locale;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use calamine::Data;
+use calamine::Reader;
+use chrono::Days;
+use chrono::NaiveDate;
+use csv::StringRecord;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Where the prayer times of a year are read from
+///
+/// New input formats only have to implement this trait to be fed to
+/// [`Generator::from_source`](super::generator::Generator::from_source).
+pub trait InputSource {
+    /// The prayer times of every day of `year` along with their month, in the order of the year
+    ///
+    /// What is left out of the input on the way, e.g. unknown columns, is added to `warnings`.
+    fn read_days(&self, year: u16, warnings: &mut Vec<String>) -> Fallible<Vec<(u8, DayInputDto)>>;
+
+    /// The directory `events.csv`, `weekly_hadith.csv` and the other side files are read from,
+    /// `None` when there are none
+    fn side_dir(&self) -> Option<PathBuf>;
+}
+
+/// A directory with one `MM.csv` file per month
+pub struct CsvDirSource {
+    pub dir: PathBuf,
+    pub columns: ColumnMapping,
+}
+
+/// A directory with one `MM.json` file per month, each one an array of days
+pub struct JsonDirSource {
+    pub dir: PathBuf,
+}
+
+/// A single CSV file with the days of the whole year, with a `date` column or `month` and `day`
+/// columns, next to the side files
+pub struct YearlyCsvSource {
+    pub path: PathBuf,
+    pub columns: ColumnMapping,
+}
+
+/// A yearly CSV read from the standard input, with the side files in `side_dir`
+pub struct StdinSource {
+    pub side_dir: Option<PathBuf>,
+    pub columns: ColumnMapping,
+}
+
+/// An `.xlsx` or `.ods` workbook, next to the side files
+///
+/// Sheets with a `date` or `month` column hold days of any month, like a yearly CSV. The other
+/// ones hold the days of a single month: the month of their name when it is a number or the name
+/// of a month, otherwise the month of their position among the monthly sheets.
+pub struct WorkbookSource {
+    pub path: PathBuf,
+    pub columns: ColumnMapping,
+}
+
+/// How the headers of the input tables are matched to the fields of the days
+///
+/// Headers are compared trimmed and lowercased, first against the ones of `columns.csv`, then
+/// against the canonical field names and their built-in aliases.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    /// `(header, field)` pairs, `field` being `ignore` for the columns to leave out
+    pub aliases: Vec<(String, String)>,
+    pub unknown_columns: UnknownColumns,
+}
+
+/// What to do with the columns that hold none of the fields of the days
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum UnknownColumns {
+    /// Leave them out silently
+    Ignore,
+    /// Leave them out and print their names
+    #[default]
+    Warn,
+    /// Refuse the table
+    Error,
+}
+
+enum Column<'a> {
+    Field(&'a str),
+    Ignored,
+    Unknown,
+}
+
+impl ColumnMapping {
+    /// Reads the aliases of `columns.csv` in `dir`, if there is one
+    pub fn load(dir: &Path, unknown_columns: UnknownColumns) -> Fallible<Self> {
+        let path = pathbuf![dir, "columns.csv"];
+        let mut aliases = vec![];
+        if path.exists() {
+            let mut reader = csv::Reader::from_path(&path)?;
+            for row in reader.deserialize::<ColumnInputDto>() {
+                let row = row.with_context(|| format!("failed to read {}", path.display()))?;
+                let field = row.field.trim().to_lowercase();
+                if field != IGNORED && !FIELDS.contains(&field.as_str()) {
+                    anyhow::bail!(
+                        "{} maps {:?} to {field:?}, which is none of {} or {IGNORED}",
+                        path.display(),
+                        row.header,
+                        FIELDS.join(", ")
+                    );
+                }
+                aliases.push((row.header.trim().to_lowercase(), field));
+            }
+        }
+        Ok(Self {
+            aliases,
+            unknown_columns,
+        })
+    }
+
+    /// What the column with `header` holds
+    fn column(&self, header: &str) -> Column<'_> {
+        let header = header.trim().to_lowercase();
+        if let Some((_, field)) = self.aliases.iter().find(|(alias, _)| *alias == header) {
+            return match field.as_str() {
+                IGNORED => Column::Ignored,
+                field => Column::Field(field),
+            };
+        }
+        if let Some(field) = FIELDS.iter().find(|field| **field == header) {
+            return Column::Field(field);
+        }
+        HEADER_ALIASES
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+            .map_or(Column::Unknown, |(field, _)| Column::Field(field))
+    }
+
+    /// Renames the headers of `table` to the fields they hold, blanking the other ones
+    fn map_headers(
+        &self,
+        headers: &StringRecord,
+        table: &str,
+        warnings: &mut Vec<String>,
+    ) -> Fallible<StringRecord> {
+        let mut mapped = StringRecord::new();
+        let mut unknown = vec![];
+        for header in headers {
+            match self.column(header) {
+                Column::Field(field) => {
+                    if let Some(other) = headers
+                        .iter()
+                        .zip(mapped.iter())
+                        .find(|(_, mapped)| *mapped == field)
+                    {
+                        anyhow::bail!(
+                            "{table}: the columns {:?} and {header:?} both hold {field}",
+                            other.0
+                        );
+                    }
+                    mapped.push_field(field);
+                }
+                Column::Ignored => mapped.push_field(""),
+                Column::Unknown => {
+                    unknown.push(format!("{header:?}"));
+                    mapped.push_field("");
+                }
+            }
+        }
+        if !unknown.is_empty() {
+            let unknown = unknown.join(", ");
+            match self.unknown_columns {
+                UnknownColumns::Ignore => {}
+                UnknownColumns::Warn => {
+                    warnings.push(format!("{table}: ignoring the unknown columns {unknown}"))
+                }
+                UnknownColumns::Error => anyhow::bail!(
+                    "{table}: unknown columns {unknown}, map them in columns.csv or set \
+                     --unknown-columns to ignore them"
+                ),
+            }
+        }
+        Ok(mapped)
+    }
+}
+
+/// Makes sure a table with the `mapped` headers holds every one of `fields`
+fn require_fields(
+    mapped: &StringRecord,
+    headers: &StringRecord,
+    fields: &[&str],
+    table: &str,
+) -> Fallible<()> {
+    let missing = fields
+        .iter()
+        .filter(|field| !mapped.iter().any(|header| header == **field))
+        .copied()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "{table}: no column holds {}, the columns are {:?}; map them in columns.csv",
+            missing.join(", "),
+            headers.iter().collect::<Vec<_>>()
+        );
+    }
+    Ok(())
+}
+
+impl InputSource for CsvDirSource {
+    fn read_days(
+        &self,
+        _year: u16,
+        warnings: &mut Vec<String>,
+    ) -> Fallible<Vec<(u8, DayInputDto)>> {
+        let mut days = vec![];
+        for month in 1..=12 {
+            let month_path = pathbuf![&self.dir, format!("{month:02}.csv")];
+            if month_path.exists() {
+                let mut reader = csv::Reader::from_path(&month_path)
+                    .with_context(|| format!("failed to read {}", month_path.display()))?;
+                let table = month_path.display().to_string();
+                let headers = reader.headers()?.clone();
+                let rows = read_records(&mut reader, &table)?;
+                let rows = read_monthly_rows(&self.columns, &headers, rows, &table, warnings)?;
+                days.extend(rows.into_iter().map(|day| (month, day)));
+            }
+        }
+        Ok(days)
+    }
+
+    fn side_dir(&self) -> Option<PathBuf> {
+        Some(self.dir.clone())
+    }
+}
+
+impl InputSource for JsonDirSource {
+    fn read_days(&self, _year: u16, _: &mut Vec<String>) -> Fallible<Vec<(u8, DayInputDto)>> {
+        let mut days = vec![];
+        for month in 1..=12 {
+            let month_path = pathbuf![&self.dir, format!("{month:02}.json")];
+            if month_path.exists() {
+                let rows: Vec<DayInputDto> = serde_json::from_reader(File::open(&month_path)?)
+                    .with_context(|| format!("failed to read {}", month_path.display()))?;
+                days.extend(rows.into_iter().map(|day| (month, day)));
+            }
+        }
+        Ok(days)
+    }
+
+    fn side_dir(&self) -> Option<PathBuf> {
+        Some(self.dir.clone())
+    }
+}
+
+impl InputSource for YearlyCsvSource {
+    fn read_days(&self, year: u16, warnings: &mut Vec<String>) -> Fallible<Vec<(u8, DayInputDto)>> {
+        let file = File::open(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        read_yearly_csv(
+            &self.columns,
+            file,
+            year,
+            &self.path.display().to_string(),
+            warnings,
+        )
+    }
+
+    fn side_dir(&self) -> Option<PathBuf> {
+        self.path.parent().map(Path::to_path_buf)
+    }
+}
+
+impl InputSource for StdinSource {
+    fn read_days(&self, year: u16, warnings: &mut Vec<String>) -> Fallible<Vec<(u8, DayInputDto)>> {
+        read_yearly_csv(
+            &self.columns,
+            std::io::stdin().lock(),
+            year,
+            "the standard input",
+            warnings,
+        )
+    }
+
+    fn side_dir(&self) -> Option<PathBuf> {
+        self.side_dir.clone()
+    }
+}
+
+impl InputSource for WorkbookSource {
+    fn read_days(&self, year: u16, warnings: &mut Vec<String>) -> Fallible<Vec<(u8, DayInputDto)>> {
+        let mut workbook = calamine::open_workbook_auto(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        let mut days = vec![];
+        // The position of the sheet among the monthly ones, notes and yearly sheets left aside
+        let mut position = 0;
+        for name in workbook.sheet_names() {
+            let range = workbook.worksheet_range(&name)?;
+            let mut rows = range
+                .rows()
+                .map(|row| row.iter().map(cell_to_string).collect::<StringRecord>());
+            let Some(headers) = rows.next() else {
+                continue;
+            };
+            // Sheets without any prayer time, e.g. notes, are not part of the timetable
+            let table = format!("the sheet {name:?} of {}", self.path.display());
+            let known = ColumnMapping {
+                unknown_columns: UnknownColumns::Ignore,
+                ..self.columns.clone()
+            };
+            let mapped = known.map_headers(&headers, &table, &mut vec![])?;
+            if !mapped.iter().any(|field| PRAYER_FIELDS.contains(&field)) {
+                continue;
+            }
+            if mapped
+                .iter()
+                .any(|field| field == "date" || field == "month")
+            {
+                days.extend(read_yearly_rows(
+                    &self.columns,
+                    &headers,
+                    rows,
+                    year,
+                    &table,
+                    warnings,
+                )?);
+                continue;
+            }
+            position += 1;
+            let month = sheet_month(&name).unwrap_or(position);
+            if !(1..=12).contains(&month) {
+                anyhow::bail!(
+                    "{table} would hold the days of month {month}, name it after the month it holds"
+                );
+            }
+            let rows = read_monthly_rows(&self.columns, &headers, rows, &table, warnings)?;
+            days.extend(rows.into_iter().map(|day| (month, day)));
+        }
+        // The days of a month can be spread over several sheets
+        check_duplicate_days(&days, &self.path.display().to_string())?;
+        days.sort_by_key(|(month, day)| (*month, day.day));
+        Ok(days)
+    }
+
+    fn side_dir(&self) -> Option<PathBuf> {
+        self.path.parent().map(Path::to_path_buf)
+    }
+}
+
+fn read_yearly_csv(
+    columns: &ColumnMapping,
+    reader: impl Read,
+    year: u16,
+    table: &str,
+    warnings: &mut Vec<String>,
+) -> Fallible<Vec<(u8, DayInputDto)>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let rows = read_records(&mut reader, table)?;
+    let mut days = read_yearly_rows(columns, &headers, rows, year, table, warnings)?;
+    days.sort_by_key(|(month, day)| (*month, day.day));
+    Ok(days)
+}
+
+fn read_monthly_rows(
+    columns: &ColumnMapping,
+    headers: &StringRecord,
+    rows: impl IntoIterator<Item = StringRecord>,
+    table: &str,
+    warnings: &mut Vec<String>,
+) -> Fallible<Vec<DayInputDto>> {
+    let mapped = columns.map_headers(headers, table, warnings)?;
+    require_fields(&mapped, headers, &["day", "hijri"], table)?;
+    require_fields(&mapped, headers, PRAYER_FIELDS, table)?;
+    deserialize_rows(&mapped, rows, table)
+}
+
+/// Reads the days of `year` out of a yearly table, leaving out the ones dated in another year
+fn read_yearly_rows(
+    columns: &ColumnMapping,
+    headers: &StringRecord,
+    rows: impl IntoIterator<Item = StringRecord>,
+    year: u16,
+    table: &str,
+    warnings: &mut Vec<String>,
+) -> Fallible<Vec<(u8, DayInputDto)>> {
+    let mapped = columns.map_headers(headers, table, warnings)?;
+    if !mapped.iter().any(|field| field == "date") {
+        require_fields(&mapped, headers, &["month", "day"], table)
+            .context("a yearly table needs a date column, or month and day columns")?;
+    }
+    require_fields(&mapped, headers, &["hijri"], table)?;
+    require_fields(&mapped, headers, PRAYER_FIELDS, table)?;
+    let mut days = vec![];
+    let mut other_years = 0;
+    for row in deserialize_rows::<YearlyDayInputDto>(&mapped, rows, table)? {
+        match row.split(year).with_context(|| format!("in {table}"))? {
+            Some(day) => days.push(day),
+            None => other_years += 1,
+        }
+    }
+    if other_years > 0 {
+        warnings.push(format!(
+            "{table}: ignoring {other_years} days that are not in {year}"
+        ));
+    }
+    check_duplicate_days(&days, table)?;
+    Ok(days)
+}
+
+/// Makes sure no day of the year is found more than once in `table`
+fn check_duplicate_days(days: &[(u8, DayInputDto)], table: &str) -> Fallible<()> {
+    let mut seen = BTreeSet::new();
+    let duplicates = days
+        .iter()
+        .map(|(month, day)| (*month, day.day))
+        .filter(|day| !seen.insert(*day))
+        .map(|(month, day)| format!("{day}/{month}"))
+        .collect::<BTreeSet<_>>();
+    if !duplicates.is_empty() {
+        anyhow::bail!(
+            "{table}: the days {} are found more than once",
+            duplicates.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn read_records(reader: &mut csv::Reader<impl Read>, table: &str) -> Fallible<Vec<StringRecord>> {
+    reader
+        .records()
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("failed to read {table}"))
+}
+
+/// Deserializes the rows of a table, failing on the first one that does not fit in `T`
+///
+/// Blank rows, like the trailing ones of spreadsheets, are left out.
+fn deserialize_rows<T: DeserializeOwned>(
+    headers: &StringRecord,
+    rows: impl IntoIterator<Item = StringRecord>,
+    table: &str,
+) -> Fallible<Vec<T>> {
+    rows.into_iter()
+        .enumerate()
+        .filter(|(_, row)| row.iter().any(|field| !field.trim().is_empty()))
+        .map(|(index, row)| {
+            // Rows are numbered like in a spreadsheet, the headers being the first one
+            let number = row
+                .position()
+                .map_or(index as u64 + 2, |position| position.line());
+            row.deserialize(Some(headers))
+                .with_context(|| format!("{table}: invalid row {number}"))
+        })
+        .collect()
+}
+
+/// The value of `columns.csv` for the columns to leave out
+const IGNORED: &str = "ignore";
+
+const PRAYER_FIELDS: &[&str] = &["fajr", "sunrise", "dhuhr", "asr", "maghrib", "ishaa"];
+
+/// Every field the days can be read from
+const FIELDS: &[&str] = &[
+    "date", "month", "day", "hijri", "fajr", "sunrise", "dhuhr", "asr", "maghrib", "ishaa",
+];
+
+/// Headers of the tables of other ministries or in Arabic, by the field they hold
+const HEADER_ALIASES: &[(&str, &[&str])] = &[
+    (
+        "date",
+        &["gregorian", "التاريخ", "الميلادي", "التاريخ الميلادي"],
+    ),
+    ("month", &["الشهر", "شهر"]),
+    ("hijri", &["الهجري", "هجري", "التاريخ الهجري"]),
+    ("fajr", &["subh", "الفجر", "فجر", "الصبح"]),
+    ("sunrise", &["shurooq", "shuruq", "الشروق", "شروق"]),
+    ("dhuhr", &["zuhr", "duhr", "thuhr", "الظهر", "ظهر"]),
+    ("asr", &["العصر", "عصر"]),
+    ("maghrib", &["المغرب", "مغرب"]),
+    ("ishaa", &["isha", "العشاء", "عشاء"]),
+];
+
+/// The month of a sheet named after it, by number or by its name, or the first letters of it, in
+/// any of the locales
+fn sheet_month(name: &str) -> Option<u8> {
+    let name = name.trim().to_lowercase();
+    if let Ok(month) = name.parse::<u8>() {
+        return Some(month);
+    }
+    if name.chars().count() < 3 {
+        return None;
+    }
+    Locale::ALL.iter().find_map(|locale| {
+        let months = locale.labels().gregorian_months;
+        let position = months
+            .iter()
+            .position(|month| month.to_lowercase().starts_with(&name))?;
+        Some(position as u8 + 1)
+    })
+}
+
+/// Writes a spreadsheet cell the way it would appear in a CSV export
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        // Times are stored as fractions of a day
+        Data::DateTime(time) if time.as_f64() < 1.0 => {
+            let minutes = (time.as_f64() * 24.0 * 60.0).round() as u32;
+            format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+        }
+        // Dates are stored as days since the end of 1899
+        Data::DateTime(date) if date.is_datetime() => {
+            let days = date.as_f64().floor() as u64;
+            NaiveDate::from_ymd_opt(1899, 12, 30)
+                .and_then(|epoch| epoch.checked_add_days(Days::new(days)))
+                .map_or_else(
+                    || date.to_string(),
+                    |date| date.format("%d/%m/%Y").to_string(),
+                )
+        }
+        // OpenDocument times are ISO 8601 durations like PT05H28M00S
+        Data::DurationIso(duration) => {
+            iso_duration_to_time(duration).unwrap_or_else(|| duration.clone())
+        }
+        Data::Float(number) if number.fract() == 0.0 => format!("{number:.0}"),
+        cell => cell.to_string(),
+    }
+}
+
+fn iso_duration_to_time(duration: &str) -> Option<String> {
+    let (hours, rest) = duration.strip_prefix("PT")?.split_once('H')?;
+    let minutes = rest.split_once('M').map_or("0", |(minutes, _)| minutes);
+    let hours = hours.parse::<u32>().ok()?;
+    let minutes = minutes.parse::<u32>().ok()?;
+    Some(format!("{hours:02}:{minutes:02}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(aliases: &[(&str, &str)], unknown_columns: UnknownColumns) -> ColumnMapping {
+        ColumnMapping {
+            aliases: aliases
+                .iter()
+                .map(|(header, field)| (header.to_string(), field.to_string()))
+                .collect(),
+            unknown_columns,
+        }
+    }
+
+    fn map(mapping: &ColumnMapping, headers: &[&str]) -> Fallible<Vec<String>> {
+        let headers = StringRecord::from(headers.to_vec());
+        let mapped = mapping.map_headers(&headers, "table", &mut vec![])?;
+        Ok(mapped.iter().map(String::from).collect())
+    }
+
+    #[test]
+    fn headers_are_mapped_through_their_aliases() {
+        let mapping = mapping(&[], UnknownColumns::Error);
+        let mapped = map(
+            &mapping,
+            &[
+                " Date ",
+                "الهجري",
+                "Subh",
+                "shurooq",
+                "ZUHR",
+                "العصر",
+                "Maghrib",
+                "isha",
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            mapped,
+            ["date", "hijri", "fajr", "sunrise", "dhuhr", "asr", "maghrib", "ishaa"]
+        );
+    }
+
+    #[test]
+    fn columns_csv_aliases_win_over_the_built_in_ones() {
+        let mapping = mapping(
+            &[("fajr", "ignore"), ("الصبح الصادق", "fajr")],
+            UnknownColumns::Error,
+        );
+        let mapped = map(&mapping, &["Fajr", "الصبح الصادق"]).unwrap();
+        assert_eq!(mapped, ["", "fajr"]);
+    }
+
+    #[test]
+    fn unknown_columns_are_blanked_or_refused() {
+        let headers = ["day", "notes", "hijri"];
+        let ignored = map(&mapping(&[], UnknownColumns::Ignore), &headers).unwrap();
+        assert_eq!(ignored, ["day", "", "hijri"]);
+        let e = map(&mapping(&[], UnknownColumns::Error), &headers).unwrap_err();
+        assert!(e.to_string().contains("unknown columns \"notes\""));
+    }
+
+    #[test]
+    fn unknown_columns_are_reported_as_warnings() {
+        let headers = StringRecord::from(vec!["day", "notes", "hijri", "remarks"]);
+        let mut warnings = vec![];
+        mapping(&[], UnknownColumns::Warn)
+            .map_headers(&headers, "table", &mut warnings)
+            .unwrap();
+        assert_eq!(
+            warnings,
+            ["table: ignoring the unknown columns \"notes\", \"remarks\""]
+        );
+    }
+
+    const YEARLY_HEADERS: &str = "date,hijri,fajr,sunrise,dhuhr,asr,maghrib,ishaa";
+
+    fn read_yearly(rows: &[&str], warnings: &mut Vec<String>) -> Fallible<Vec<(u8, u16)>> {
+        let csv = [YEARLY_HEADERS]
+            .iter()
+            .chain(rows)
+            .copied()
+            .collect::<Vec<_>>();
+        let days = read_yearly_csv(
+            &ColumnMapping::default(),
+            csv.join("\n").as_bytes(),
+            2025,
+            "table",
+            warnings,
+        )?;
+        Ok(days
+            .into_iter()
+            .map(|(month, day)| (month, day.day))
+            .collect())
+    }
+
+    #[test]
+    fn yearly_rows_of_other_years_are_left_out() {
+        let mut warnings = vec![];
+        let days = read_yearly(
+            &[
+                "30/12/2024,29/6/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+                "31/12/2024,30/6/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+                "2025-01-01,1/7/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+                "31/12/2025,11/7/1447,05:40,07:00,12:00,14:30,16:50,18:10",
+            ],
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(days, [(1, 1), (12, 31)]);
+        assert_eq!(warnings, ["table: ignoring 2 days that are not in 2025"]);
+    }
+
+    #[test]
+    fn duplicate_yearly_days_are_refused() {
+        let e = read_yearly(
+            &[
+                "1/1/2025,1/7/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+                "2025-01-01,1/7/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+            ],
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("the days 1/1 are found more than once"));
+    }
+
+    #[test]
+    fn sheets_are_named_after_their_month() {
+        assert_eq!(sheet_month(" 03 "), Some(3));
+        assert_eq!(sheet_month("13"), Some(13));
+        assert_eq!(sheet_month("January"), Some(1));
+        assert_eq!(sheet_month("sep"), Some(9));
+        assert_eq!(sheet_month("Février"), Some(2));
+        assert_eq!(sheet_month("ديسمبر"), Some(12));
+        assert_eq!(sheet_month("Notes"), None);
+        assert_eq!(sheet_month("Ma"), None);
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_with_their_number() {
+        let csv = [
+            "month,day,hijri,fajr,sunrise,dhuhr,asr,maghrib,ishaa",
+            "1,1,1/7/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+            "Jan,2,2/7/1446,05:40,07:00,12:00,14:30,16:50,18:10",
+        ]
+        .join("\n");
+        let e = read_yearly_csv(
+            &ColumnMapping::default(),
+            csv.as_bytes(),
+            2025,
+            "table",
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "table: invalid row 3");
+    }
+
+    #[test]
+    fn two_columns_cannot_hold_the_same_field() {
+        let e = map(&mapping(&[], UnknownColumns::Ignore), &["fajr", "subh"]).unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("the columns \"fajr\" and \"subh\" both hold fajr"));
+    }
+}
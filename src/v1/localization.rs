@@ -0,0 +1,258 @@
+use super::domain::minutes_since_midnight;
+use super::output_dtos::LabelsOutputDto;
+use super::output_dtos::PrayerTimesOutputDto;
+use super::output_dtos::WeekdayLabelsOutputDto;
+
+/// The languages the labels shown next to the index can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Locale {
+    Ar,
+    En,
+    Fr,
+    Tr,
+}
+
+/// The digits the dates and times of the display block are written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Numerals {
+    /// 0123456789, the ones of the canonical values
+    Latin,
+    /// ٠١٢٣٤٥٦٧٨٩
+    EasternArabic,
+}
+
+/// How the times of the display block are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TimeFormat {
+    /// 17:05, the format of the canonical values
+    #[cfg_attr(feature = "cli", value(name = "24h"))]
+    H24,
+    /// 5:05 PM
+    #[cfg_attr(feature = "cli", value(name = "12h"))]
+    H12,
+    /// 5:05, as in printed timetables
+    #[cfg_attr(feature = "cli", value(name = "12h-bare"))]
+    H12Bare,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::Ar, Locale::En, Locale::Fr, Locale::Tr];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::Ar => "ar",
+            Locale::En => "en",
+            Locale::Fr => "fr",
+            Locale::Tr => "tr",
+        }
+    }
+
+    pub fn labels(&self) -> LabelsOutputDto {
+        let (gregorian_months, hijri_months, weekdays, prayers) = match self {
+            Locale::Ar => (
+                [
+                    "يناير",
+                    "فبراير",
+                    "مارس",
+                    "أبريل",
+                    "مايو",
+                    "يونيو",
+                    "يوليو",
+                    "أغسطس",
+                    "سبتمبر",
+                    "أكتوبر",
+                    "نوفمبر",
+                    "ديسمبر",
+                ],
+                [
+                    "محرم",
+                    "صفر",
+                    "ربيع الأول",
+                    "ربيع الآخر",
+                    "جمادى الأولى",
+                    "جمادى الآخرة",
+                    "رجب",
+                    "شعبان",
+                    "رمضان",
+                    "شوال",
+                    "ذو القعدة",
+                    "ذو الحجة",
+                ],
+                [
+                    "الاثنين",
+                    "الثلاثاء",
+                    "الأربعاء",
+                    "الخميس",
+                    "الجمعة",
+                    "السبت",
+                    "الأحد",
+                ],
+                ["الفجر", "الشروق", "الظهر", "العصر", "المغرب", "العشاء"],
+            ),
+            Locale::En => (
+                [
+                    "January",
+                    "February",
+                    "March",
+                    "April",
+                    "May",
+                    "June",
+                    "July",
+                    "August",
+                    "September",
+                    "October",
+                    "November",
+                    "December",
+                ],
+                [
+                    "Muharram",
+                    "Safar",
+                    "Rabi' al-Awwal",
+                    "Rabi' al-Thani",
+                    "Jumada al-Ula",
+                    "Jumada al-Akhirah",
+                    "Rajab",
+                    "Sha'ban",
+                    "Ramadan",
+                    "Shawwal",
+                    "Dhu al-Qi'dah",
+                    "Dhu al-Hijjah",
+                ],
+                [
+                    "Monday",
+                    "Tuesday",
+                    "Wednesday",
+                    "Thursday",
+                    "Friday",
+                    "Saturday",
+                    "Sunday",
+                ],
+                ["Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"],
+            ),
+            Locale::Fr => (
+                [
+                    "janvier",
+                    "février",
+                    "mars",
+                    "avril",
+                    "mai",
+                    "juin",
+                    "juillet",
+                    "août",
+                    "septembre",
+                    "octobre",
+                    "novembre",
+                    "décembre",
+                ],
+                [
+                    "Mouharram",
+                    "Safar",
+                    "Rabi' al-Awwal",
+                    "Rabi' ath-Thani",
+                    "Joumada al-Oula",
+                    "Joumada ath-Thania",
+                    "Rajab",
+                    "Chaabane",
+                    "Ramadan",
+                    "Chawwal",
+                    "Dhou al-Qi'da",
+                    "Dhou al-Hijja",
+                ],
+                [
+                    "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+                ],
+                ["Fajr", "Lever du soleil", "Dhuhr", "Asr", "Maghrib", "Icha"],
+            ),
+            Locale::Tr => (
+                [
+                    "Ocak", "Şubat", "Mart", "Nisan", "Mayıs", "Haziran", "Temmuz", "Ağustos",
+                    "Eylül", "Ekim", "Kasım", "Aralık",
+                ],
+                [
+                    "Muharrem",
+                    "Safer",
+                    "Rebiülevvel",
+                    "Rebiülahir",
+                    "Cemaziyelevvel",
+                    "Cemaziyelahir",
+                    "Recep",
+                    "Şaban",
+                    "Ramazan",
+                    "Şevval",
+                    "Zilkade",
+                    "Zilhicce",
+                ],
+                [
+                    "Pazartesi",
+                    "Salı",
+                    "Çarşamba",
+                    "Perşembe",
+                    "Cuma",
+                    "Cumartesi",
+                    "Pazar",
+                ],
+                ["Sabah", "Güneş", "Öğle", "İkindi", "Akşam", "Yatsı"],
+            ),
+        };
+        let [mon, tue, wed, thu, fri, sat, sun] = weekdays.map(String::from);
+        let [fajr, sunrise, dhuhr, asr, maghrib, ishaa] = prayers.map(String::from);
+        LabelsOutputDto {
+            locale: self.code().to_string(),
+            gregorian_months: gregorian_months.map(String::from).to_vec(),
+            hijri_months: hijri_months.map(String::from).to_vec(),
+            weekdays: WeekdayLabelsOutputDto {
+                mon,
+                tue,
+                wed,
+                thu,
+                fri,
+                sat,
+                sun,
+            },
+            prayers: PrayerTimesOutputDto {
+                fajr,
+                sunrise,
+                dhuhr,
+                asr,
+                maghrib,
+                ishaa,
+            },
+        }
+    }
+}
+
+impl Numerals {
+    /// Rewrites the digits of `text` with these numerals
+    pub fn render(&self, text: &str) -> String {
+        match self {
+            Numerals::Latin => text.to_string(),
+            Numerals::EasternArabic => text
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(digit) => char::from_u32('٠' as u32 + digit).unwrap_or(c),
+                    None => c,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TimeFormat {
+    /// Rewrites a `HH:MM` time in this format, leaving anything else as it is
+    pub fn render(&self, time: &str) -> String {
+        let Some(minutes) = minutes_since_midnight(time) else {
+            return time.to_string();
+        };
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        let hours_12 = if hours % 12 == 0 { 12 } else { hours % 12 };
+        let suffix = if hours < 12 { "AM" } else { "PM" };
+        match self {
+            TimeFormat::H24 => time.to_string(),
+            TimeFormat::H12 => format!("{hours_12}:{minutes:02} {suffix}"),
+            TimeFormat::H12Bare => format!("{hours_12}:{minutes:02}"),
+        }
+    }
+}
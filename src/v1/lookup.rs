@@ -0,0 +1,120 @@
+use super::domain::minutes_since_midnight;
+use super::output_dtos::DayOutputDto;
+use super::output_dtos::NextPrayerOutputDto;
+use super::output_dtos::YearOutputDto;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use chrono::Datelike;
+use chrono::Days;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Looks days up in a generated output tree
+pub struct OutputReader {
+    pub v1_dir: PathBuf,
+}
+
+impl OutputReader {
+    /// Reads the tree of `output_dir`, either the directory passed to `-o` or the `v1` directory
+    /// inside of it
+    pub fn new(output_dir: &Path) -> Self {
+        let v1_dir = pathbuf![output_dir, "v1"];
+        Self {
+            v1_dir: if v1_dir.is_dir() {
+                v1_dir
+            } else {
+                output_dir.to_path_buf()
+            },
+        }
+    }
+
+    /// The day at `date`, out of its day index or else out of the yearly one
+    pub fn day(&self, date: NaiveDate) -> Fallible<Option<DayOutputDto>> {
+        let day_path = pathbuf![
+            &self.v1_dir,
+            "day",
+            date.year().to_string(),
+            format!("{:02}", date.month()),
+            format!("{:02}.json", date.day())
+        ];
+        if let Some(day) = self.load(&day_path)? {
+            return Ok(Some(day));
+        }
+        Ok(self
+            .year(date.year() as u16)?
+            .and_then(|days| days.into_iter().find(|day| day.id == day_id(date))))
+    }
+
+    /// Every day of `year`, `None` when there is no yearly index for it
+    pub fn year(&self, year: u16) -> Fallible<Option<Vec<DayOutputDto>>> {
+        let year_path = pathbuf![&self.v1_dir, "year", "days", format!("{year}.json")];
+        Ok(self
+            .load::<YearOutputDto>(&year_path)?
+            .map(|year_idx| year_idx.year))
+    }
+
+    fn load<T: DeserializeOwned>(&self, path: &Path) -> Fallible<Option<T>> {
+        match File::open(path) {
+            Ok(file) => serde_json::from_reader(file)
+                .map(Some)
+                .with_context(|| format!("failed to parse {}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("failed to open {}", path.display())),
+        }
+    }
+}
+
+/// The id of the day at `date`, e.g. `20250314`
+pub fn day_id(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10000 + u64::from(date.month() * 100 + date.day())
+}
+
+/// Parses a local time like `2025-03-14T04:50`
+pub fn parse_at(at: &str) -> Fallible<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(at, "%Y-%m-%dT%H:%M")
+        .with_context(|| format!("{at:?} is not a time like 2025-03-14T04:50"))
+}
+
+/// The first prayer after `at`, out of the day of `at` and the day after it, sunrise not being a
+/// prayer
+pub fn next_prayer(
+    day_of: impl Fn(NaiveDate) -> Fallible<Option<DayOutputDto>>,
+    at: NaiveDateTime,
+) -> Fallible<Option<NextPrayerOutputDto>> {
+    let now = i64::from(at.hour() * 60 + at.minute());
+    for offset in 0..=1 {
+        let Some(date) = at.date().checked_add_days(Days::new(offset)) else {
+            break;
+        };
+        let Some(day) = day_of(date)? else {
+            continue;
+        };
+        let next = day
+            .prayer_times
+            .named()
+            .into_iter()
+            .filter(|(prayer, _)| *prayer != "sunrise")
+            .filter_map(|(prayer, time)| Some((prayer, time, minutes_since_midnight(time)?)))
+            .map(|(prayer, time, minutes)| {
+                let minutes_until = offset as i64 * 24 * 60 + i64::from(minutes) - now;
+                (prayer, time, minutes_until)
+            })
+            .find(|(_, _, minutes_until)| *minutes_until > 0);
+        if let Some((prayer, time, minutes_until)) = next {
+            return Ok(Some(NextPrayerOutputDto {
+                id: day.id,
+                gregorian: day.gregorian,
+                prayer: prayer.to_string(),
+                time: time.to_string(),
+                minutes_until,
+            }));
+        }
+    }
+    Ok(None)
+}
@@ -1,5 +1,25 @@
+pub mod catalog;
+pub mod compact;
+pub mod compression;
+#[cfg(feature = "cli")]
+pub mod config;
+pub mod diff;
 pub mod domain;
 pub mod generator;
+pub mod index;
 pub mod input_dtos;
+pub mod input_source;
+pub mod localization;
+pub mod lookup;
 pub mod output_dtos;
+pub mod output_format;
+#[cfg(feature = "cli")]
 pub mod params;
+#[cfg(feature = "cli")]
+pub mod query;
+#[cfg(feature = "cli")]
+pub mod serve;
+pub mod sqlite;
+pub mod verify;
+#[cfg(feature = "cli")]
+pub mod watch;
@@ -1,21 +1,65 @@
+use super::domain;
+use super::domain::format_minutes;
+use super::domain::minutes_since_midnight;
+use super::domain::DailyContent;
+use super::domain::DailyContentKind;
 use super::domain::DailyPrayerTime;
+use super::domain::DayDisplay;
+use super::domain::Event;
+use super::domain::EventCategory;
+use super::domain::Hadith;
+use super::domain::HijriDate;
+use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct YearOutputDto {
     pub year: Vec<DayOutputDto>,
     pub sha1: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct YearWeeksOutputDto {
     pub weeks: Vec<WeekOutputDto>,
     pub sha1: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RamadanOutputDto {
+    pub hijri_year: u16,
+    pub days: Vec<RamadanDayOutputDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RamadanDayOutputDto {
+    /// The day of Ramadan, starting at 1
+    pub fast_day: u8,
+    pub id: u64,
+    pub gregorian: String,
+    pub hijri: String,
+    /// The end of suhoor, some minutes before fajr
+    pub imsak: Option<String>,
+    pub fajr: String,
+    /// Iftar time
+    pub maghrib: String,
+    /// Minutes between fajr and maghrib
+    pub fasting_minutes: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventOutputDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WeekOutputDto {
     pub id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
     pub mon: Option<DayOutputDto>,
     pub tue: Option<DayOutputDto>,
     pub wed: Option<DayOutputDto>,
@@ -26,14 +70,15 @@ pub struct WeekOutputDto {
     pub hadith: Option<HaidthOutputDto>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HaidthOutputDto {
     pub hadith: String,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct DayOutputDto {
     pub id: u64,
     pub gregorian: String,
@@ -41,11 +86,56 @@ pub struct DayOutputDto {
     pub prayer_times: PrayerTimesOutputDto,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub week_id: Option<u64>,
+    /// The hadith of the week the day belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hadith: Option<HaidthOutputDto>,
+    /// The reminder of the day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<DailyContentOutputDto>,
+    /// The dates and prayer times as shown to people, next to the canonical ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplayOutputDto>,
+    /// The first of `events`, kept for clients that predate multiple events per day
     #[serde(skip_serializing_if = "Option::is_none")]
     pub event: Option<EventOutputDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventOutputDto>,
+}
+
+/// The next prayer after a given time, as answered by `ptig v1 serve` and `ptig v1 query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NextPrayerOutputDto {
+    /// The id of the day the prayer falls on
+    pub id: u64,
+    pub gregorian: String,
+    pub prayer: String,
+    pub time: String,
+    pub minutes_until: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DisplayOutputDto {
+    pub gregorian: String,
+    pub hijri: String,
+    pub prayer_times: PrayerTimesOutputDto,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DailyContentOutputDto {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DailyContentKind>,
+    pub ar: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PrayerTimesOutputDto {
     pub fajr: String,
     pub sunrise: String,
@@ -55,11 +145,186 @@ pub struct PrayerTimesOutputDto {
     pub ishaa: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct EventOutputDto {
     pub ar: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub en: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<EventCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventsOutputDto {
+    pub events: Vec<EventsEntryOutputDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventsEntryOutputDto {
+    pub start_id: u64,
+    pub end_id: u64,
+    pub start: String,
+    pub end: String,
+    pub hijri_start: String,
+    pub hijri_end: String,
+    pub ar: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub en: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<EventCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LabelsOutputDto {
+    pub locale: String,
+    /// From January to December
+    pub gregorian_months: Vec<String>,
+    /// From Muharram to Dhu al-Hijjah
+    pub hijri_months: Vec<String>,
+    pub weekdays: WeekdayLabelsOutputDto,
+    pub prayers: PrayerTimesOutputDto,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeekdayLabelsOutputDto {
+    pub mon: String,
+    pub tue: String,
+    pub wed: String,
+    pub thu: String,
+    pub fri: String,
+    pub sat: String,
+    pub sun: String,
+}
+
+impl WeekOutputDto {
+    /// The days of the week that fall within the year
+    pub fn days(&self) -> impl Iterator<Item = &DayOutputDto> {
+        [
+            &self.mon, &self.tue, &self.wed, &self.thu, &self.fri, &self.sat, &self.sun,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl DayOutputDto {
+    /// The events of the day, also for days read from outputs that only had `event`
+    pub fn all_events(&self) -> Vec<EventOutputDto> {
+        if self.events.is_empty() {
+            self.event.iter().cloned().collect()
+        } else {
+            self.events.clone()
+        }
+    }
+}
+
+impl EventsEntryOutputDto {
+    /// An occurrence of `event` running from the `first` to the `last` day
+    pub fn new(event: &Event, first: DayOutputDto, last: DayOutputDto) -> Self {
+        Self {
+            start_id: first.id,
+            end_id: last.id,
+            start: first.gregorian,
+            end: last.gregorian,
+            hijri_start: first.hijri,
+            hijri_end: last.hijri,
+            ar: event.ar.clone(),
+            en: event.en.clone(),
+            category: event.category,
+        }
+    }
+}
+
+impl From<Hadith> for HaidthOutputDto {
+    fn from(hadith: Hadith) -> Self {
+        Self {
+            hadith: hadith.hadith,
+            note: hadith.note,
+        }
+    }
+}
+
+impl From<DayDisplay> for DisplayOutputDto {
+    fn from(display: DayDisplay) -> Self {
+        Self {
+            gregorian: display.gregorian,
+            hijri: display.hijri,
+            prayer_times: display.prayer_times.into(),
+        }
+    }
+}
+
+impl From<domain::PrayerTimes> for PrayerTimesOutputDto {
+    fn from(prayer_times: domain::PrayerTimes) -> Self {
+        Self {
+            fajr: prayer_times.fajr,
+            sunrise: prayer_times.sunrise,
+            dhuhr: prayer_times.dhuhr,
+            asr: prayer_times.asr,
+            maghrib: prayer_times.maghrib,
+            ishaa: prayer_times.ishaa,
+        }
+    }
+}
+
+impl From<DailyContent> for DailyContentOutputDto {
+    fn from(content: DailyContent) -> Self {
+        Self {
+            kind: content.kind,
+            ar: content.ar,
+            translation: content.translation,
+            source: content.source,
+        }
+    }
+}
+
+impl From<Event> for EventOutputDto {
+    fn from(event: Event) -> Self {
+        Self {
+            ar: event.ar,
+            en: event.en,
+            category: event.category,
+        }
+    }
+}
+
+impl RamadanDayOutputDto {
+    pub fn new(day: DayOutputDto, imsak_offset: u16) -> Self {
+        let fajr = minutes_since_midnight(&day.prayer_times.fajr);
+        let maghrib = minutes_since_midnight(&day.prayer_times.maghrib);
+        Self {
+            fast_day: HijriDate::parse(&day.hijri).map_or(0, |hijri| hijri.day),
+            id: day.id,
+            gregorian: day.gregorian,
+            hijri: day.hijri,
+            imsak: fajr.map(|fajr| format_minutes(i32::from(fajr) - i32::from(imsak_offset))),
+            fajr: day.prayer_times.fajr,
+            maghrib: day.prayer_times.maghrib,
+            fasting_minutes: fajr
+                .zip(maghrib)
+                .and_then(|(fajr, maghrib)| maghrib.checked_sub(fajr)),
+            events: day.events,
+        }
+    }
+}
+
+impl PrayerTimesOutputDto {
+    /// Pairs every prayer time with the name of its prayer
+    pub fn named(&self) -> [(&'static str, &str); 6] {
+        [
+            ("fajr", &self.fajr),
+            ("sunrise", &self.sunrise),
+            ("dhuhr", &self.dhuhr),
+            ("asr", &self.asr),
+            ("maghrib", &self.maghrib),
+            ("ishaa", &self.ishaa),
+        ]
+    }
 }
 
 impl From<DailyPrayerTime> for DayOutputDto {
@@ -85,10 +350,11 @@ impl From<DailyPrayerTime> for DayOutputDto {
         )
         .parse()
         .unwrap_or_default();
-        let event = match day.event {
-            None => None,
-            Some(e) => Some(EventOutputDto { ar: e.ar, en: e.en }),
-        };
+        let events = day
+            .events
+            .into_iter()
+            .map(EventOutputDto::from)
+            .collect::<Vec<_>>();
 
         Self {
             id,
@@ -98,15 +364,63 @@ impl From<DailyPrayerTime> for DayOutputDto {
             ),
             hijri,
             week_id: day.week_id,
+            hadith: day.hadith.map(Into::into),
+            content: day.content.map(Into::into),
+            display: day.display.map(Into::into),
+            prayer_times: day.prayer_times.into(),
+            event: events.first().cloned(),
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(hijri: &str, fajr: &str, maghrib: &str) -> DayOutputDto {
+        DayOutputDto {
+            id: 20250303,
+            gregorian: "03/03/2025".to_string(),
+            hijri: hijri.to_string(),
             prayer_times: PrayerTimesOutputDto {
-                fajr: day.prayer_times.fajr,
-                sunrise: day.prayer_times.sunrise,
-                dhuhr: day.prayer_times.dhuhr,
-                asr: day.prayer_times.asr,
-                maghrib: day.prayer_times.maghrib,
-                ishaa: day.prayer_times.ishaa,
+                fajr: fajr.to_string(),
+                sunrise: "06:20".to_string(),
+                dhuhr: "12:30".to_string(),
+                asr: "15:50".to_string(),
+                maghrib: maghrib.to_string(),
+                ishaa: "19:50".to_string(),
             },
-            event,
+            week_id: None,
+            hadith: None,
+            content: None,
+            display: None,
+            event: None,
+            events: vec![],
         }
     }
+
+    #[test]
+    fn ramadan_days_have_imsak_and_fasting_minutes() {
+        let ramadan_day = RamadanDayOutputDto::new(day("3/9/1446", "04:05", "18:35"), 10);
+        assert_eq!(ramadan_day.fast_day, 3);
+        assert_eq!(ramadan_day.imsak.as_deref(), Some("03:55"));
+        assert_eq!(ramadan_day.fasting_minutes, Some(870));
+    }
+
+    #[test]
+    fn imsak_wraps_around_midnight() {
+        let ramadan_day = RamadanDayOutputDto::new(day("1/9/1446", "00:05", "18:35"), 10);
+        assert_eq!(ramadan_day.imsak.as_deref(), Some("23:55"));
+    }
+
+    #[test]
+    fn unparsable_times_leave_imsak_and_fasting_minutes_out() {
+        let ramadan_day = RamadanDayOutputDto::new(day("1/9/1446", "--", "18:35"), 10);
+        assert_eq!(ramadan_day.imsak, None);
+        assert_eq!(ramadan_day.fasting_minutes, None);
+        let ramadan_day = RamadanDayOutputDto::new(day("1/9/1446", "04:05", "--"), 10);
+        assert_eq!(ramadan_day.imsak.as_deref(), Some("03:55"));
+        assert_eq!(ramadan_day.fasting_minutes, None);
+    }
 }
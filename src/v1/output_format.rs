@@ -0,0 +1,47 @@
+use crate::prelude::Fallible;
+use serde::Serialize;
+
+/// The encodings every index can be written in, each one next to the others with the same layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    Json,
+    Cbor,
+    Msgpack,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 3] = [
+        OutputFormat::Json,
+        OutputFormat::Cbor,
+        OutputFormat::Msgpack,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Cbor => "cbor",
+            OutputFormat::Msgpack => "msgpack",
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T, minify: bool) -> Fallible<Vec<u8>> {
+        match self {
+            OutputFormat::Json => {
+                let json = serde_json::to_value(value)?;
+                if minify {
+                    Ok(serde_json::to_vec(&json)?)
+                } else {
+                    Ok(serde_json::to_vec_pretty(&json)?)
+                }
+            }
+            OutputFormat::Cbor => {
+                let mut bytes = vec![];
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+            // Maps keep their field names so the documents mirror the json ones
+            OutputFormat::Msgpack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+}
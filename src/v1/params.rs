@@ -1,77 +1,645 @@
+use super::compression::Compression;
+use super::config::ConfigFile;
+use super::config::ConfigSettings;
+use super::diff::YearDiff;
+use super::domain::Adjustment;
+use super::domain::WeekDay;
+use super::domain::WeekScheme;
 use super::generator::Generator;
+use super::index::IndexKind;
+use super::input_source::ColumnMapping;
+use super::input_source::CsvDirSource;
+use super::input_source::InputSource;
+use super::input_source::JsonDirSource;
+use super::input_source::StdinSource;
+use super::input_source::UnknownColumns;
+use super::input_source::WorkbookSource;
+use super::input_source::YearlyCsvSource;
+use super::localization::Locale;
+use super::localization::Numerals;
+use super::localization::TimeFormat;
+use super::lookup::parse_at;
+use super::output_dtos::DayOutputDto;
+use super::output_format::OutputFormat;
+use super::query::query;
+use super::serve::serve;
+use super::verify::verify;
+use super::watch::watch;
 use crate::prelude::Fallible;
+use crate::sink::ArchiveSink;
+use crate::sink::FsSink;
+use crate::staging::StagingDir;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use clap::Args;
 use clap::Parser;
+use clap::Subcommand;
 use clap::ValueEnum;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct V1Params {
-    /// The year to generate the prayer times for
+    #[command(subcommand)]
+    pub command: Option<V1Command>,
+    #[command(flatten)]
+    pub generate: GenerateParams,
+}
+
+/// The options of a run, each one falling back to `ptig.toml` when it is not given
+///
+/// The switches take an optional value, so that `--minify=false` turns off a `minify = true` of
+/// the project file.
+#[derive(Debug, Clone, Default, PartialEq, Args)]
+pub struct GenerateParams {
+    /// The project file to read the options from, `ptig.toml` in the current directory when
+    /// there is one
+    #[clap(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Only run the region of the project file with this name instead of every one of them
+    #[clap(long)]
+    pub region: Option<String>,
+    /// The year to generate the prayer times for, instead of the `years` of the project file
     #[clap(short = 'y', long = "year")]
-    pub year: u16,
-    /// Path to directory containing 12 json files, each one of them contains the prayer times for
-    /// the month
+    pub year: Option<u16>,
+    /// Path to the directory containing the 12 monthly files, or to the yearly file, along with
+    /// the side files like `events.csv`
     #[clap(short = 'i', long = "input")]
-    pub year_dir: PathBuf,
+    pub year_dir: Option<PathBuf>,
     /// Where to save the output directory
     #[clap(short = 'o', long = "output")]
-    pub output_dir_path: PathBuf,
+    pub output_dir_path: Option<PathBuf>,
+    /// Write the output to a `.tar`, `.tar.gz` or `.zip` archive instead of the output directory,
+    /// starting from the files already in the output directory when it is given
+    #[clap(long, value_name = "FILE")]
+    pub archive: Option<PathBuf>,
     /// Input format
     #[clap(short = 'f', long = "format")]
-    pub input_format: InputFormat,
-    /// The day of the week that the generated index will start with
-    #[clap(short = 's', long, default_value = "sat")]
-    pub week_start_day: WeekDay,
+    pub input_format: Option<InputFormat>,
+    /// What to do with the input columns that no field is read from [default: warn]
+    #[clap(long)]
+    pub unknown_columns: Option<UnknownColumns>,
+    /// The clock the prayer times of the input are written with [default: 24h]
+    #[clap(long)]
+    pub input_clock: Option<InputClock>,
+    /// The day of the week that the generated index will start with [default: sat]
+    #[clap(short = 's', long)]
+    pub week_start_day: Option<WeekDay>,
+    /// How the days of the year are split into weeks [default: chunked]
+    #[clap(long)]
+    pub week_scheme: Option<WeekScheme>,
+    /// Leave out the built-in catalog of hijri occasions, only keeping `hijri_events.csv`
+    #[clap(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub no_builtin_events: Option<bool>,
+    /// How many minutes before fajr imsak is in the Ramadan timetable [default: 10]
+    #[clap(long)]
+    pub imsak_offset: Option<u16>,
+    /// Minutes added to prayer times of every day, e.g. `fajr=-2,maghrib=3` for a nearby city
+    #[clap(long, value_delimiter = ',', value_name = "PRAYER=MINUTES")]
+    pub adjust: Vec<Adjustment>,
+    /// Encodings to write every index in, next to each other [default: json]
+    #[clap(long = "output-format", value_delimiter = ',')]
+    pub output_formats: Vec<OutputFormat>,
+    /// Also write `year/compact/<year>.bin`, a fixed-width binary table of the year
+    #[clap(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub compact_year: Option<bool>,
+    /// Also write `sqlite/<year>.sqlite`, a SQLite database of the whole index, on top of the
+    /// picked indexes
+    #[clap(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub sqlite: Option<bool>,
+    /// The indexes to write, e.g. `year,sha1` for a lightweight bundle [default: every index but
+    /// sqlite]
+    #[clap(long = "index", value_delimiter = ',')]
+    pub indexes: Vec<IndexKind>,
+    /// Write compact json instead of pretty printed json
+    #[clap(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub minify: Option<bool>,
+    /// Precompressed sidecars to write next to every output file
+    #[clap(long, value_delimiter = ',')]
+    pub compress: Vec<Compression>,
+    /// Also write `labels/<locale>.json` with the month, weekday and prayer names of each locale
+    #[clap(long = "locale", value_delimiter = ',')]
+    pub locales: Vec<Locale>,
+    /// Add a display block to every day with its dates and prayer times written in these
+    /// numerals [default: latin]
+    #[clap(long)]
+    pub numerals: Option<Numerals>,
+    /// Add a display block to every day with its prayer times written in this format
+    /// [default: 24h]
+    #[clap(long)]
+    pub time_format: Option<TimeFormat>,
+    /// Load and validate the input without writing anything to the output directory
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Print the changes against a previously generated output directory
+    #[clap(long, value_name = "OLD_OUTPUT_DIR")]
+    pub diff: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum V1Command {
+    /// Re-check a generated output tree for consistency
+    Verify(VerifyParams),
+    /// Regenerate the output every time a file of the input changes
+    Watch(Box<WatchParams>),
+    /// Serve an output tree on localhost to preview it from a client
+    Serve(ServeParams),
+    /// Look up the prayer times of a day, a range of days or the next prayer
+    Query(QueryParams),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct VerifyParams {
+    /// The output directory that was passed to the generator
+    #[clap(short = 'o', long = "output")]
+    pub output_dir_path: PathBuf,
+    /// Only verify this year instead of every year found in the output
+    #[clap(short = 'y', long = "year")]
+    pub year: Option<u16>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ServeParams {
+    /// The output directory that was passed to the generator
+    #[clap(short = 'o', long = "output")]
+    pub output_dir_path: PathBuf,
+    /// The address to listen on
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: String,
+    #[clap(short = 'p', long, default_value = "8080")]
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct QueryParams {
+    /// The project file to read the input from, `ptig.toml` in the current directory when there
+    /// is one
+    #[clap(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// The region of the project file to query
+    #[clap(long)]
+    pub region: Option<String>,
+    /// The year of the input, the one of the queried day by default
+    #[clap(short = 'y', long = "year")]
+    pub year: Option<u16>,
+    /// The input directory or file to query
+    #[clap(short = 'i', long = "input")]
+    pub year_dir: Option<PathBuf>,
+    #[clap(short = 'f', long = "format")]
+    pub input_format: Option<InputFormat>,
+    /// How the prayer times of the input are written, 24h by default
+    #[clap(long)]
+    pub input_clock: Option<InputClock>,
+    /// Minutes added to a prayer time of every day, e.g. `fajr=-2`
+    #[clap(long, value_delimiter = ',', value_name = "PRAYER=MINUTES")]
+    pub adjust: Vec<Adjustment>,
+    /// A generated output directory to query instead of the input
+    #[clap(short = 'o', long = "output", conflicts_with = "year_dir")]
+    pub output_dir_path: Option<PathBuf>,
+    /// The day to look up, today by default
+    #[clap(long, conflicts_with_all = ["from", "next"])]
+    pub date: Option<NaiveDate>,
+    /// The first day of a range to look up
+    #[clap(long, requires = "to", conflicts_with = "next")]
+    pub from: Option<NaiveDate>,
+    /// The last day of the range, included
+    #[clap(long, requires = "from")]
+    pub to: Option<NaiveDate>,
+    /// Look up the next prayer instead of a day
+    #[clap(long)]
+    pub next: bool,
+    /// The time to look up the next prayer from, like 2025-03-14T04:50, now by default
+    #[clap(long, requires = "next", value_parser = parse_at)]
+    pub at: Option<NaiveDateTime>,
+    /// Print JSON instead of a table
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatchParams {
+    #[command(flatten)]
+    pub generate: GenerateParams,
+    /// How many milliseconds to wait for the input to settle before regenerating
+    #[clap(long, default_value = "300")]
+    pub debounce: u64,
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
 pub enum InputFormat {
+    /// A directory with one `MM.json` file per month
     Json,
+    /// A directory with one `MM.csv` file per month
     Csv,
+    /// A single CSV file with a `date` column, or `month` and `day` columns
+    YearlyCsv,
+    /// An `.xlsx` or `.ods` workbook, with one sheet per month or a single sheet with a `date`
+    /// column
+    #[value(alias = "xlsx", alias = "ods")]
+    Workbook,
+    /// A yearly CSV on the standard input, the input directory only holding the side files
+    Stdin,
 }
 
-#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
-pub enum WeekDay {
-    Sun,
-    Mon,
-    Tue,
-    Wed,
-    Thu,
-    Fri,
-    Sat,
-}
-
-impl WeekDay {
-    pub fn previous(&self) -> Self {
-        match self {
-            WeekDay::Sun => WeekDay::Sat,
-            WeekDay::Mon => WeekDay::Sun,
-            WeekDay::Tue => WeekDay::Mon,
-            WeekDay::Wed => WeekDay::Tue,
-            WeekDay::Thu => WeekDay::Wed,
-            WeekDay::Fri => WeekDay::Thu,
-            WeekDay::Sat => WeekDay::Fri,
-        }
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum InputClock {
+    /// `HH:MM` times, copied as they are
+    #[value(name = "24h")]
+    H24,
+    /// `H:MM` times with or without an AM/PM suffix, the bare ones being put in the half of the
+    /// day their prayer falls in
+    #[value(name = "12h")]
+    H12,
+}
+
+impl InputFormat {
+    /// The source reading `input` in this format, with the `columns.csv` found next to it
+    pub fn source(
+        &self,
+        input: PathBuf,
+        unknown_columns: UnknownColumns,
+    ) -> Fallible<Box<dyn InputSource>> {
+        // The side files sit in the input directory, or next to the input file
+        let side_dir = if input.is_dir() {
+            input.clone()
+        } else {
+            input.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let columns = ColumnMapping::load(&side_dir, unknown_columns)?;
+        Ok(match self {
+            InputFormat::Json => Box::new(JsonDirSource { dir: input }),
+            InputFormat::Csv => Box::new(CsvDirSource {
+                dir: input,
+                columns,
+            }),
+            InputFormat::YearlyCsv => Box::new(YearlyCsvSource {
+                path: input,
+                columns,
+            }),
+            InputFormat::Workbook => Box::new(WorkbookSource {
+                path: input,
+                columns,
+            }),
+            InputFormat::Stdin => Box::new(StdinSource {
+                side_dir: Some(input),
+                columns,
+            }),
+        })
     }
 }
 
 impl V1Params {
-    pub fn generate(&self) -> Fallible<()> {
-        let mut generator = Generator::new(
-            self.year,
-            self.year_dir.clone(),
-            pathbuf![&self.output_dir_path, "v1"],
+    pub fn run(&self) -> Fallible<()> {
+        match &self.command {
+            Some(V1Command::Verify(params)) => verify(&params.output_dir_path, params.year),
+            Some(V1Command::Watch(params)) => {
+                watch(&params.generate, Duration::from_millis(params.debounce))
+            }
+            Some(V1Command::Serve(params)) => {
+                serve(&params.output_dir_path, &params.host, params.port)
+            }
+            Some(V1Command::Query(params)) => query(params),
+            None => {
+                for params in self.generate.with_config()? {
+                    params.generate()?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl QueryParams {
+    /// The options to read the input with, the other ones being left to `ptig.toml`
+    pub fn generate_params(&self) -> GenerateParams {
+        GenerateParams {
+            config: self.config.clone(),
+            region: self.region.clone(),
+            year: self.year,
+            year_dir: self.year_dir.clone(),
+            input_format: self.input_format.clone(),
+            input_clock: self.input_clock,
+            adjust: self.adjust.clone(),
+            ..GenerateParams::default()
+        }
+    }
+}
+
+impl GenerateParams {
+    /// Every run to generate: these options over each region and year of the project file, or
+    /// these options alone when there is no project file
+    ///
+    /// A `ptig.toml` that was only found in the current directory does not take over a run whose
+    /// input or output is given on the command line, it only fills its other options with its
+    /// top-level settings.
+    pub fn with_config(&self) -> Fallible<Vec<GenerateParams>> {
+        let Some(config) = ConfigFile::discover(self.config.as_deref())? else {
+            return Ok(vec![self.clone()]);
+        };
+        let given_output = self.output_dir_path.is_some() || self.archive.is_some();
+        let expand = self.config.is_some()
+            || self.region.is_some()
+            || (self.year_dir.is_none() && !given_output);
+        let settings = if expand {
+            config.runs(self.region.as_deref())?
+        } else {
+            vec![(None, &config.settings)]
+        };
+        let mut runs = vec![];
+        for (region, settings) in settings {
+            let years = match (self.year, &settings.years) {
+                (Some(year), _) => vec![year],
+                (None, Some(years)) if expand => years.list()?,
+                (None, Some(years)) => match years.list()?.as_slice() {
+                    [year] => vec![*year],
+                    _ => anyhow::bail!(
+                        "--year is required when the input or output is given on the command line"
+                    ),
+                },
+                (None, None) => anyhow::bail!(
+                    "--year is required when {} has no years",
+                    config.path.display()
+                ),
+            };
+            for year in years {
+                runs.push(self.merged(&config, region, settings, year));
+            }
+        }
+        check_outputs(&runs)?;
+        Ok(runs)
+    }
+
+    /// The region and year of the run, to tell it apart from the other runs
+    pub fn describe(&self) -> String {
+        let year = self.year.map(|year| year.to_string()).unwrap_or_default();
+        match &self.region {
+            Some(region) => format!("{region} {year}"),
+            None => year,
+        }
+    }
+
+    /// The picked indexes in the order they are generated
+    pub fn indexes(&self) -> Vec<IndexKind> {
+        let mut picked = if self.indexes.is_empty() {
+            IndexKind::defaults()
+        } else {
+            self.indexes.clone()
+        };
+        if self.sqlite.unwrap_or_default() {
+            picked.push(IndexKind::Sqlite);
+        }
+        IndexKind::ALL
+            .into_iter()
+            .filter(|index| picked.contains(index))
+            .collect()
+    }
+
+    /// These options, falling back to `settings` for the ones that are not given
+    fn merged(
+        &self,
+        config: &ConfigFile,
+        region: Option<&str>,
+        settings: &ConfigSettings,
+        year: u16,
+    ) -> GenerateParams {
+        let path = |template: &Option<String>| {
+            template
+                .as_ref()
+                .map(|template| config.path(template, region, year))
+        };
+        // The adjustments of the flags replace the ones of the file prayer by prayer
+        let mut adjust = settings
+            .adjustments
+            .iter()
+            .filter(|(prayer, _)| !self.adjust.iter().any(|a| a.prayer == **prayer))
+            .map(|(prayer, minutes)| Adjustment {
+                prayer: *prayer,
+                minutes: *minutes,
+            })
+            .collect::<Vec<_>>();
+        adjust.extend(self.adjust.iter().copied());
+        GenerateParams {
+            config: self.config.clone(),
+            region: region.map(String::from),
+            year: Some(year),
+            year_dir: self.year_dir.clone().or_else(|| path(&settings.input)),
+            output_dir_path: self
+                .output_dir_path
+                .clone()
+                .or_else(|| path(&settings.output)),
+            archive: self.archive.clone().or_else(|| path(&settings.archive)),
+            input_format: self.input_format.clone().or(settings.format.clone()),
+            unknown_columns: self.unknown_columns.or(settings.unknown_columns),
+            input_clock: self.input_clock.or(settings.input_clock),
+            week_start_day: self
+                .week_start_day
+                .clone()
+                .or(settings.week_start_day.clone()),
+            week_scheme: self.week_scheme.clone().or(settings.week_scheme.clone()),
+            no_builtin_events: self.no_builtin_events.or(settings.no_builtin_events),
+            imsak_offset: self.imsak_offset.or(settings.imsak_offset),
+            adjust,
+            output_formats: or_file(&self.output_formats, &settings.output_formats),
+            compact_year: self.compact_year.or(settings.compact_year),
+            sqlite: self.sqlite.or(settings.sqlite),
+            indexes: or_file(&self.indexes, &settings.indexes),
+            minify: self.minify.or(settings.minify),
+            compress: or_file(&self.compress, &settings.compress),
+            locales: or_file(&self.locales, &settings.locales),
+            numerals: self.numerals.or(settings.numerals),
+            time_format: self.time_format.or(settings.time_format),
+            dry_run: self.dry_run,
+            diff: self.diff.clone(),
+        }
+    }
+
+    /// Reads the input and computes every day of the year, as they would be generated
+    pub fn load(&self) -> Fallible<Generator> {
+        // Either given as flags or found in the project file
+        let (Some(year), Some(year_dir), Some(input_format)) =
+            (self.year, &self.year_dir, &self.input_format)
+        else {
+            anyhow::bail!("--year, --input and --format are required");
+        };
+        let source =
+            input_format.source(year_dir.clone(), self.unknown_columns.unwrap_or_default())?;
+        let mut generator = Generator::from_source(
+            year,
+            source.as_ref(),
+            !self.no_builtin_events.unwrap_or_default(),
+        )?;
+        for warning in &generator.warnings {
+            eprintln!("warning: {warning}");
+        }
+        if self.input_clock == Some(InputClock::H12) {
+            generator.resolve_12_hour_times()?;
+        }
+        generator.adjust_times(&self.adjust)?;
+        generator.make_weeks(
+            self.week_start_day.clone().unwrap_or(WeekDay::Sat),
+            self.week_scheme.clone().unwrap_or(WeekScheme::Chunked),
         )?;
-        match self.input_format {
-            InputFormat::Json => {}
-            InputFormat::Csv => {
-                generator.generate_daily_prayer_times()?;
-                generator.generate_weekly_prayer_times(self.week_start_day.clone())?;
-                generator.generate_yearly_prayer_times()?;
-                generator.generate_monthly_prayer_times()?;
-                generator.generate_sha1()?;
+        generator.make_display(
+            self.numerals.unwrap_or(Numerals::Latin),
+            self.time_format.unwrap_or(TimeFormat::H24),
+        );
+        Ok(generator)
+    }
+
+    pub fn generate(&self) -> Fallible<()> {
+        let mut generator = self.load()?;
+        if generator.yearly_prayer_times.is_empty() {
+            anyhow::bail!(
+                "no day of {} was found in the input",
+                self.year.unwrap_or_default()
+            );
+        }
+        if let (Some(old_output_dir), Some(year)) = (&self.diff, self.year) {
+            let days = generator
+                .yearly_prayer_times
+                .iter()
+                .cloned()
+                .map(DayOutputDto::from)
+                .collect();
+            let diff = YearDiff::against(old_output_dir, year, days, &generator.weeks)?;
+            print!("{diff}");
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let output_dir = self
+            .output_dir_path
+            .as_ref()
+            .map(|path| pathbuf![path, "v1"]);
+        let staging = match (&self.archive, output_dir) {
+            (Some(archive), output_dir) => {
+                let sink = ArchiveSink::new(archive.clone(), PathBuf::from("v1"))?;
+                if let Some(output_dir) = output_dir.filter(|dir| dir.is_dir()) {
+                    sink.seed_from_dir(&output_dir)?;
+                }
+                generator.sink = Box::new(sink);
+                None
+            }
+            (None, Some(output_dir)) => {
+                // Everything is generated into a staging copy of the output and only swapped into
+                // place once every index has been written
+                let staging = StagingDir::new(output_dir)?;
+                generator.sink = Box::new(FsSink::new(staging.path()));
+                Some(staging)
             }
+            (None, None) => anyhow::bail!("--output or --archive is required"),
+        };
+        generator.formats = if self.output_formats.is_empty() {
+            vec![OutputFormat::Json]
+        } else {
+            self.output_formats.clone()
+        };
+        generator.compact_year = self.compact_year.unwrap_or_default();
+        generator.minify = self.minify.unwrap_or_default();
+        generator.compressions = self.compress.clone();
+        generator.imsak_offset = self.imsak_offset.unwrap_or(10);
+        generator.locales = self.locales.clone();
+        for index in self.indexes() {
+            index.generate(&generator)?;
         }
-        Ok(())
+        generator.sink.finish()?;
+        match staging {
+            Some(staging) => staging.commit(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Makes sure no two runs write the same year of an output directory, or the same archive which
+/// only ever holds the files of its last run
+fn check_outputs(runs: &[GenerateParams]) -> Fallible<()> {
+    for (i, run) in runs.iter().enumerate() {
+        for other in &runs[..i] {
+            let same_dir = run.year == other.year
+                && run.archive.is_none()
+                && other.archive.is_none()
+                && same_path(&run.output_dir_path, &other.output_dir_path)?;
+            let same_archive = same_path(&run.archive, &other.archive)?;
+            if same_dir || same_archive {
+                anyhow::bail!(
+                    "the runs {} and {} would write the same output, give each region its own \
+                     with {{region}} in the path",
+                    other.describe(),
+                    run.describe()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn same_path(path: &Option<PathBuf>, other: &Option<PathBuf>) -> Fallible<bool> {
+    match (path, other) {
+        (Some(path), Some(other)) => Ok(std::path::absolute(path)? == std::path::absolute(other)?),
+        _ => Ok(false),
+    }
+}
+
+/// The values of a list flag, or the ones of the project file when the flag is not given
+fn or_file<T: Clone>(cli: &[T], file: &Option<Vec<T>>) -> Vec<T> {
+    match file {
+        Some(file) if cli.is_empty() => file.clone(),
+        _ => cli.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(settings: ConfigSettings) -> ConfigFile {
+        ConfigFile {
+            path: PathBuf::from("project/ptig.toml"),
+            settings,
+            regions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn cli_switches_override_the_file_both_ways() {
+        let config = config(ConfigSettings {
+            minify: Some(true),
+            sqlite: Some(false),
+            ..ConfigSettings::default()
+        });
+        let params = GenerateParams {
+            minify: Some(false),
+            sqlite: Some(true),
+            ..GenerateParams::default()
+        };
+        let run = params.merged(&config, None, &config.settings, 2025);
+        assert_eq!(run.minify, Some(false));
+        assert_eq!(run.sqlite, Some(true));
+        let run = GenerateParams::default().merged(&config, None, &config.settings, 2025);
+        assert_eq!(run.minify, Some(true));
+        assert_eq!(run.sqlite, Some(false));
+    }
+
+    #[test]
+    fn runs_writing_the_same_output_are_refused() {
+        let run = |region: &str, year: u16, output: &str| GenerateParams {
+            region: Some(region.to_string()),
+            year: Some(year),
+            output_dir_path: Some(PathBuf::from(output)),
+            ..GenerateParams::default()
+        };
+        check_outputs(&[run("a", 2025, "out/a"), run("b", 2025, "out/b")]).unwrap();
+        check_outputs(&[run("a", 2025, "out"), run("a", 2026, "out")]).unwrap();
+        let e = check_outputs(&[run("a", 2025, "out"), run("b", 2025, "./out")]).unwrap_err();
+        assert!(e.to_string().starts_with("the runs a 2025 and b 2025"));
+
+        let archive = |region: &str, year: u16| GenerateParams {
+            archive: Some(PathBuf::from("v1.zip")),
+            ..run(region, year, "out")
+        };
+        assert!(check_outputs(&[archive("a", 2025), archive("a", 2026)]).is_err());
     }
 }
@@ -0,0 +1,194 @@
+use super::config::ConfigFile;
+use super::lookup::day_id;
+use super::lookup::next_prayer;
+use super::lookup::OutputReader;
+use super::output_dtos::DayOutputDto;
+use super::output_dtos::NextPrayerOutputDto;
+use super::params::GenerateParams;
+use super::params::QueryParams;
+use crate::prelude::Fallible;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Where the days are looked up
+enum Source {
+    /// A generated output tree
+    Output(OutputReader),
+    /// The input, read the same way as when generating it
+    Input(Box<GenerateParams>),
+}
+
+/// The days of a source, each year being read once
+struct Dataset {
+    source: Source,
+    years: RefCell<BTreeMap<u16, Option<Vec<DayOutputDto>>>>,
+}
+
+/// Prints the day, the days or the next prayer asked by `params`, as a table or as JSON
+pub fn query(params: &QueryParams) -> Fallible<()> {
+    let now = Local::now().naive_local();
+    let at = params.at.unwrap_or(now);
+    let (from, to) = match (params.date, params.from, params.to) {
+        (_, Some(from), Some(to)) => (from, to),
+        (Some(date), _, _) => (date, date),
+        _ => (now.date(), now.date()),
+    };
+    if from > to {
+        anyhow::bail!("--from {from} is after --to {to}");
+    }
+    let source = match &params.output_dir_path {
+        Some(output_dir) => Source::Output(OutputReader::new(output_dir)),
+        None => {
+            let mut generate = params.generate_params();
+            if generate.year.is_none()
+                && ConfigFile::discover(generate.config.as_deref())?.is_none()
+            {
+                // Without a project file the input holds a single year, the one asked about
+                let first = if params.next { at.date() } else { from };
+                generate.year = u16::try_from(first.year()).ok();
+            }
+            Source::Input(Box::new(generate))
+        }
+    };
+    let dataset = Dataset {
+        source,
+        years: RefCell::default(),
+    };
+
+    if params.next {
+        let Some(next) = next_prayer(|date| dataset.day(date), at)? else {
+            anyhow::bail!("there is no prayer after {at} in the dataset");
+        };
+        if params.json {
+            return print_json(&next);
+        }
+        print_next_prayer(&next);
+        return Ok(());
+    }
+
+    let mut days = vec![];
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        match dataset.day(date)? {
+            Some(day) => days.push(day),
+            None => anyhow::bail!("there is no day {date} in the dataset"),
+        }
+    }
+    match (params.json, days.as_slice()) {
+        (true, [day]) if params.from.is_none() => print_json(day),
+        (true, _) => print_json(&days),
+        (false, _) => {
+            print_days(&days);
+            Ok(())
+        }
+    }
+}
+
+impl Dataset {
+    fn day(&self, date: NaiveDate) -> Fallible<Option<DayOutputDto>> {
+        let Ok(year) = u16::try_from(date.year()) else {
+            return Ok(None);
+        };
+        if !self.years.borrow().contains_key(&year) {
+            let days = self.load_year(year)?;
+            self.years.borrow_mut().insert(year, days);
+        }
+        if let Some(days) = &self.years.borrow()[&year] {
+            return Ok(days.iter().find(|day| day.id == day_id(date)).cloned());
+        }
+        match &self.source {
+            // The output may only hold the day index
+            Source::Output(reader) => reader.day(date),
+            Source::Input(_) => Ok(None),
+        }
+    }
+
+    fn load_year(&self, year: u16) -> Fallible<Option<Vec<DayOutputDto>>> {
+        let params = match &self.source {
+            Source::Output(reader) => return reader.year(year),
+            // The input only holds the year it was given for
+            Source::Input(params) if params.year.is_some_and(|only| only != year) => {
+                return Ok(None)
+            }
+            Source::Input(params) => GenerateParams {
+                year: Some(year),
+                ..GenerateParams::clone(params)
+            },
+        };
+        let runs = params.with_config()?;
+        let [run] = runs.as_slice() else {
+            anyhow::bail!("the project file has several regions, pick one with --region");
+        };
+        let Some(input) = &run.year_dir else {
+            anyhow::bail!("--input or --output is required");
+        };
+        // The input of the next year is only needed if there is one, e.g. for the next prayer
+        if !input.exists() {
+            return Ok(None);
+        }
+        Ok(Some(run.load()?.render_days()))
+    }
+}
+
+fn print_json(value: &impl Serialize) -> Fallible<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn print_days(days: &[DayOutputDto]) {
+    let mut rows = vec![vec!["date".to_string(), "hijri".to_string()]];
+    rows[0].extend(
+        days[0]
+            .prayer_times
+            .named()
+            .map(|(prayer, _)| prayer.to_string()),
+    );
+    for day in days {
+        let mut row = vec![day.gregorian.clone(), day.hijri.clone()];
+        row.extend(day.prayer_times.named().map(|(_, time)| time.to_string()));
+        rows.push(row);
+    }
+    print_table(&rows);
+}
+
+fn print_next_prayer(next: &NextPrayerOutputDto) {
+    let minutes = next.minutes_until;
+    let rows = [
+        ["date", "prayer", "time", "in"].map(String::from),
+        [
+            next.gregorian.clone(),
+            next.prayer.clone(),
+            next.time.clone(),
+            format!("{}h{:02}", minutes / 60, minutes % 60),
+        ],
+    ];
+    print_table(&rows);
+}
+
+/// Prints `rows` as left-aligned columns, the first one being the header
+fn print_table(rows: &[impl AsRef<[String]>]) {
+    let mut widths = vec![];
+    for row in rows {
+        for (i, cell) in row.as_ref().iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(max) if *max < width => *max = width,
+                Some(_) => {}
+                None => widths.push(width),
+            }
+        }
+    }
+    for row in rows {
+        let line = row
+            .as_ref()
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
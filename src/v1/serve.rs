@@ -0,0 +1,232 @@
+use super::compression::Compression;
+use super::lookup::next_prayer;
+use super::lookup::parse_at;
+use super::lookup::OutputReader;
+use crate::prelude::Fallible;
+use chrono::Local;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+
+/// A response before it is encoded for the client
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+    /// The precompressed sidecars the body can be swapped with
+    sidecars: Vec<(Compression, PathBuf)>,
+}
+
+/// Serves the output tree under `output_dir` on `host:port` until interrupted, along with the
+/// `/v1/today` and `/v1/next-prayer` routes computed from it
+///
+/// Meant to preview the index from a client during development, not to be put in production.
+pub fn serve(output_dir: &Path, host: &str, port: u16) -> Fallible<()> {
+    let reader = OutputReader::new(output_dir);
+    if !reader.v1_dir.is_dir() {
+        anyhow::bail!("{} is not a directory", output_dir.display());
+    }
+    let server = Server::http((host, port)).map_err(|e| anyhow::anyhow!(e))?;
+    println!(
+        "Serving {} on http://{host}:{port}/v1/",
+        reader.v1_dir.display()
+    );
+    for request in server.incoming_requests() {
+        if let Err(e) = respond(&reader, request) {
+            eprintln!("error: failed to respond: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn respond(reader: &OutputReader, request: Request) -> Fallible<()> {
+    let cors = [
+        header("Access-Control-Allow-Origin", "*"),
+        header("Access-Control-Allow-Methods", "GET, HEAD, OPTIONS"),
+        header("Access-Control-Allow-Headers", "*"),
+    ];
+    if *request.method() == Method::Options {
+        let mut response = Response::empty(204);
+        for header in cors {
+            response.add_header(header);
+        }
+        return Ok(request.respond(response)?);
+    }
+
+    let reply = match request.method() {
+        Method::Get | Method::Head => route(reader, request.url()),
+        _ => Ok(error(405, "only GET, HEAD and OPTIONS are supported")),
+    };
+    let reply = reply.unwrap_or_else(|e| error(500, &format!("{e:#}")));
+    let accepted = accepted_encodings(&request);
+    let (body, encoding) = encode(&reply, &accepted)?;
+
+    let mut response = Response::from_data(body).with_status_code(reply.status);
+    response.add_header(header("Content-Type", reply.content_type));
+    response.add_header(header("Vary", "Accept-Encoding"));
+    if let Some(encoding) = encoding {
+        response.add_header(header("Content-Encoding", encoding.content_encoding()));
+    }
+    for header in cors {
+        response.add_header(header);
+    }
+    Ok(request.respond(response)?)
+}
+
+fn route(reader: &OutputReader, url: &str) -> Fallible<Reply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    };
+    match path.trim_end_matches('/') {
+        "/v1/today" => {
+            let date = match param("date") {
+                Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                    Ok(date) => date,
+                    Err(_) => return Ok(error(400, "date must be like 2025-03-14")),
+                },
+                None => Local::now().date_naive(),
+            };
+            match reader.day(date)? {
+                Some(day) => json(&day),
+                None => Ok(error(404, &format!("there is no day {date} in the index"))),
+            }
+        }
+        "/v1/next-prayer" => {
+            let at = match param("at") {
+                Some(at) => match parse_at(at) {
+                    Ok(at) => at,
+                    Err(_) => return Ok(error(400, "at must be like 2025-03-14T04:50")),
+                },
+                None => Local::now().naive_local(),
+            };
+            match next_prayer(|date| reader.day(date), at)? {
+                Some(next) => json(&next),
+                None => Ok(error(
+                    404,
+                    &format!("there is no prayer after {at} in the index"),
+                )),
+            }
+        }
+        _ => file(reader, path),
+    }
+}
+
+/// The file at `url_path` under the `v1` directory of the output
+fn file(reader: &OutputReader, url_path: &str) -> Fallible<Reply> {
+    let Some(relative) = url_path.strip_prefix("/v1/") else {
+        return Ok(error(404, "only /v1/ is served"));
+    };
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Ok(error(404, "no such file"));
+    }
+    let path = reader.v1_dir.join(relative);
+    if !path.is_file() {
+        return Ok(error(404, "no such file"));
+    }
+    let sidecars = Compression::ALL
+        .into_iter()
+        .map(|compression| {
+            let mut sidecar = path.clone().into_os_string();
+            sidecar.push(format!(".{}", compression.extension()));
+            (compression, PathBuf::from(sidecar))
+        })
+        .filter(|(_, sidecar)| sidecar.is_file())
+        .collect();
+    Ok(Reply {
+        status: 200,
+        content_type: content_type(&path),
+        body: std::fs::read(&path)?,
+        sidecars,
+    })
+}
+
+/// The body to send given the encodings the client accepts, preferring the precompressed
+/// sidecars and else gzipping on the fly
+fn encode(reply: &Reply, accepted: &[Compression]) -> Fallible<(Vec<u8>, Option<Compression>)> {
+    // Brotli first, since it makes the smallest files
+    for compression in [Compression::Brotli, Compression::Gzip] {
+        if !accepted.contains(&compression) {
+            continue;
+        }
+        if let Some((_, sidecar)) = reply.sidecars.iter().find(|(c, _)| *c == compression) {
+            return Ok((std::fs::read(sidecar)?, Some(compression)));
+        }
+    }
+    if accepted.contains(&Compression::Gzip) && !reply.body.is_empty() {
+        return Ok((
+            Compression::Gzip.compress(&reply.body)?,
+            Some(Compression::Gzip),
+        ));
+    }
+    Ok((reply.body.clone(), None))
+}
+
+fn accepted_encodings(request: &Request) -> Vec<Compression> {
+    request
+        .headers()
+        .iter()
+        .filter(|header| header.field.equiv("Accept-Encoding"))
+        .flat_map(|header| header.value.as_str().split(','))
+        .filter_map(|encoding| {
+            let (name, params) = encoding.split_once(';').unwrap_or((encoding, ""));
+            if params.replace(' ', "") == "q=0" {
+                return None;
+            }
+            Compression::ALL
+                .into_iter()
+                .find(|compression| compression.content_encoding() == name.trim())
+        })
+        .collect()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => "application/json",
+        Some("cbor") => "application/cbor",
+        Some("msgpack") => "application/msgpack",
+        Some("sqlite") => "application/vnd.sqlite3",
+        Some("csv") => "text/csv",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn json(value: &impl Serialize) -> Fallible<Reply> {
+    Ok(Reply {
+        status: 200,
+        content_type: "application/json",
+        body: serde_json::to_vec(value)?,
+        sidecars: vec![],
+    })
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    Reply {
+        status,
+        content_type: "application/json",
+        body: serde_json::json!({ "error": message })
+            .to_string()
+            .into_bytes(),
+        sidecars: vec![],
+    }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("headers are ascii")
+}
@@ -0,0 +1,143 @@
+use super::domain::minutes_since_midnight;
+use super::domain::DailyPrayerTime;
+use super::output_dtos::DayOutputDto;
+use crate::prelude::Fallible;
+use chrono::Datelike;
+use rusqlite::params;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+const SCHEMA: &str = "
+CREATE TABLE days (
+    id INTEGER PRIMARY KEY,
+    date TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    month INTEGER NOT NULL,
+    day INTEGER NOT NULL,
+    weekday TEXT,
+    hijri TEXT NOT NULL,
+    hijri_year INTEGER,
+    hijri_month INTEGER,
+    hijri_day INTEGER,
+    week_id INTEGER REFERENCES weeks (id)
+);
+CREATE TABLE prayer_times (
+    day_id INTEGER NOT NULL REFERENCES days (id),
+    prayer TEXT NOT NULL,
+    time TEXT NOT NULL,
+    minutes INTEGER,
+    PRIMARY KEY (day_id, prayer)
+);
+CREATE TABLE events (
+    day_id INTEGER NOT NULL REFERENCES days (id),
+    ar TEXT NOT NULL,
+    en TEXT,
+    category TEXT
+);
+CREATE TABLE weeks (
+    id INTEGER PRIMARY KEY,
+    year INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    start_day_id INTEGER NOT NULL,
+    end_day_id INTEGER NOT NULL
+);
+CREATE TABLE hadith (
+    week_id INTEGER PRIMARY KEY REFERENCES weeks (id),
+    hadith TEXT NOT NULL,
+    note TEXT
+);
+CREATE TABLE daily_content (
+    day_id INTEGER PRIMARY KEY REFERENCES days (id),
+    kind TEXT,
+    ar TEXT NOT NULL,
+    translation TEXT,
+    source TEXT
+);
+CREATE INDEX days_hijri ON days (hijri_year, hijri_month, hijri_day);
+CREATE INDEX prayer_times_prayer ON prayer_times (prayer, minutes);
+";
+
+/// Builds a SQLite database of the year, expecting the weeks to be laid out already
+pub fn export_year(days: &[DailyPrayerTime]) -> Fallible<Vec<u8>> {
+    let mut conn = Connection::open_in_memory()?;
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+
+    // The weeks go first as the days reference them
+    let mut weeks = BTreeMap::new();
+    for day in days {
+        if let Some(week_id) = day.week_id {
+            let id = DayOutputDto::from(day.clone()).id;
+            let (_, end, _) = weeks.entry(week_id).or_insert((id, id, &day.hadith));
+            *end = id;
+        }
+    }
+    for (week_id, (start, end, hadith)) in weeks {
+        // Week ids are made of the year followed by a two digit week number
+        let number = (week_id % 100) as u16;
+        // SQLite integers are signed, the ids always fit
+        let week_id = week_id as i64;
+        tx.execute(
+            "INSERT INTO weeks VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![week_id, week_id / 100, number, start as i64, end as i64],
+        )?;
+        if let Some(hadith) = hadith {
+            tx.execute(
+                "INSERT INTO hadith VALUES (?1, ?2, ?3)",
+                params![week_id, hadith.hadith, hadith.note],
+            )?;
+        }
+    }
+
+    for day in days {
+        let dto = DayOutputDto::from(day.clone());
+        let date = &day.gregorian_date;
+        let weekday = date
+            .to_naive_date()
+            .map(|date| date.weekday().to_string().to_lowercase());
+        let hijri = day.hijri();
+        let id = dto.id as i64;
+        tx.execute(
+            "INSERT INTO days VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                id,
+                format!("{}-{:02}-{:02}", date.year, date.month, date.day),
+                date.year,
+                date.month,
+                date.day,
+                weekday,
+                dto.hijri,
+                hijri.map(|h| h.year),
+                hijri.map(|h| h.month),
+                hijri.map(|h| h.day),
+                day.week_id.map(|id| id as i64),
+            ],
+        )?;
+        for (prayer, time) in dto.prayer_times.named() {
+            tx.execute(
+                "INSERT INTO prayer_times VALUES (?1, ?2, ?3, ?4)",
+                params![id, prayer, time, minutes_since_midnight(time)],
+            )?;
+        }
+        if let Some(content) = &day.content {
+            tx.execute(
+                "INSERT INTO daily_content VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    id,
+                    content.kind.map(|k| k.as_str()),
+                    content.ar,
+                    content.translation,
+                    content.source
+                ],
+            )?;
+        }
+        for event in dto.events {
+            tx.execute(
+                "INSERT INTO events VALUES (?1, ?2, ?3, ?4)",
+                params![id, event.ar, event.en, event.category.map(|c| c.as_str())],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(conn.serialize("main")?.to_vec())
+}
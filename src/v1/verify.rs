@@ -0,0 +1,312 @@
+use super::domain::minutes_since_midnight;
+use super::generator::Generator;
+use super::lookup::OutputReader;
+use super::output_dtos::DayOutputDto;
+use super::output_dtos::YearOutputDto;
+use super::output_dtos::YearWeeksOutputDto;
+use crate::prelude::Fallible;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::read_dir;
+use std::fs::File;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Re-checks a published output tree against its own yearly day index
+pub struct Verifier {
+    pub output_dir: PathBuf,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Sha1OutputDto {
+    sha1: String,
+}
+
+impl Verifier {
+    pub fn new(output_dir: PathBuf) -> Self {
+        Self {
+            output_dir,
+            problems: vec![],
+        }
+    }
+
+    /// Lists the years that have a yearly day index in the output tree
+    pub fn years(&self) -> Fallible<Vec<u16>> {
+        let year_dir = pathbuf![&self.output_dir, "year", "days"];
+        // Runs without the year index leave nothing to verify against
+        if !year_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut years = read_dir(year_dir)?
+            .flatten()
+            .flat_map(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .strip_suffix(".json")
+                    .and_then(|year| year.parse().ok())
+            })
+            .collect::<Vec<u16>>();
+        years.sort();
+        Ok(years)
+    }
+
+    pub fn verify_year(&mut self, year: u16) {
+        let Some((year_idx, year_value)) = self.load::<YearOutputDto>(pathbuf![
+            &self.output_dir,
+            "year",
+            "days",
+            format!("{year}.json")
+        ]) else {
+            return;
+        };
+        let day_values = match year_value.get("year") {
+            Some(Value::Array(days)) => days.clone(),
+            _ => vec![],
+        };
+        for day in &year_idx.year {
+            self.check_day_fields(day);
+        }
+
+        match Generator::hash_days(&year_idx.year) {
+            Ok(sha1) => self.verify_sha1(year, &year_idx, &sha1),
+            Err(e) => self.problem(format!("failed to hash the days of {year}: {e}")),
+        }
+        self.verify_days(year, &year_idx.year, &day_values);
+        self.verify_months(year, &year_idx.year, &day_values);
+        self.verify_weeks(year, &year_idx.year, &day_values);
+    }
+
+    fn verify_sha1(&mut self, year: u16, year_idx: &YearOutputDto, sha1: &str) {
+        if year_idx.sha1 != sha1 {
+            self.problem(format!(
+                "year/days/{year}.json: sha1 {} does not match its days ({sha1})",
+                year_idx.sha1
+            ));
+        }
+        let sha1_path = pathbuf![&self.output_dir, "sha1", format!("{year}.json")];
+        if let Some((published, _)) = self.load_generated::<Sha1OutputDto>(sha1_path) {
+            if published.sha1 != sha1 {
+                self.problem(format!(
+                    "sha1/{year}.json: sha1 {} does not match year/days/{year}.json ({sha1})",
+                    published.sha1
+                ));
+            }
+        }
+        let weeks_path = pathbuf![&self.output_dir, "year", "weeks", format!("{year}.json")];
+        if let Some((weeks, _)) = self.load_generated::<YearWeeksOutputDto>(weeks_path) {
+            if weeks.sha1 != sha1 {
+                self.problem(format!(
+                    "year/weeks/{year}.json: sha1 {} does not match year/days/{year}.json ({sha1})",
+                    weeks.sha1
+                ));
+            }
+        }
+    }
+
+    fn verify_days(&mut self, year: u16, days: &[DayOutputDto], day_values: &[Value]) {
+        // The day index was left out of the run
+        if !pathbuf![&self.output_dir, "day", year.to_string()].is_dir() {
+            return;
+        }
+        for (day, expected) in days.iter().zip(day_values) {
+            let (year, month, day_of_month) = Self::split_id(day.id);
+            let day_path = pathbuf![
+                &self.output_dir,
+                "day",
+                year.to_string(),
+                format!("{month:02}"),
+                format!("{day_of_month:02}.json")
+            ];
+            let Some((_, actual)) = self.load::<DayOutputDto>(day_path) else {
+                continue;
+            };
+            if actual != *expected {
+                self.problem(format!(
+                    "day/{year}/{month:02}/{day_of_month:02}.json does not match year/days/{year}.json"
+                ));
+            }
+        }
+    }
+
+    fn verify_months(&mut self, year: u16, days: &[DayOutputDto], day_values: &[Value]) {
+        if !pathbuf![&self.output_dir, "month", year.to_string()].is_dir() {
+            return;
+        }
+        for month in 1..=12 {
+            let expected = days
+                .iter()
+                .zip(day_values)
+                .filter(|(day, _)| Self::split_id(day.id).1 == month)
+                .map(|(_, value)| value.clone())
+                .collect::<Vec<_>>();
+            let month_path = pathbuf![
+                &self.output_dir,
+                "month",
+                year.to_string(),
+                format!("{month:02}.json")
+            ];
+            let Some((_, actual)) = self.load::<Vec<DayOutputDto>>(month_path) else {
+                continue;
+            };
+            if actual != Value::Array(expected) {
+                self.problem(format!(
+                    "month/{year}/{month:02}.json does not match year/days/{year}.json"
+                ));
+            }
+        }
+    }
+
+    fn verify_weeks(&mut self, year: u16, days: &[DayOutputDto], day_values: &[Value]) {
+        let weeks_path = pathbuf![&self.output_dir, "year", "weeks", format!("{year}.json")];
+        let Some((weeks, _)) = self.load_generated::<YearWeeksOutputDto>(weeks_path) else {
+            return;
+        };
+        let expected = days
+            .iter()
+            .zip(day_values)
+            .map(|(day, value)| (day.id, (day, value)))
+            .collect::<HashMap<_, _>>();
+        let mut seen = HashMap::new();
+        for week in weeks.weeks {
+            for day in week.days() {
+                *seen.entry(day.id).or_insert(0) += 1;
+                let Some((expected_day, expected_value)) = expected.get(&day.id) else {
+                    self.problem(format!(
+                        "year/weeks/{year}.json: week {} has day {} that is not in year/days/{year}.json",
+                        week.id, day.id
+                    ));
+                    continue;
+                };
+                if expected_day.week_id.is_some_and(|id| id != week.id) {
+                    self.problem(format!(
+                        "year/weeks/{year}.json: day {} is in week {} but its week id is {:?}",
+                        day.id, week.id, expected_day.week_id
+                    ));
+                }
+                if serde_json::to_value(day).ok().as_ref() != Some(*expected_value) {
+                    self.problem(format!(
+                        "year/weeks/{year}.json: day {} does not match year/days/{year}.json",
+                        day.id
+                    ));
+                }
+            }
+        }
+        for day in days {
+            match seen.get(&day.id) {
+                Some(1) => {}
+                Some(n) => self.problem(format!(
+                    "year/weeks/{year}.json: day {} appears in {n} weeks",
+                    day.id
+                )),
+                // Some week schemes leave the days around the first and last weeks out
+                None if day.week_id.is_none() => {}
+                None => self.problem(format!(
+                    "year/weeks/{year}.json: day {} is not in any week",
+                    day.id
+                )),
+            }
+        }
+    }
+
+    fn check_day_fields(&mut self, day: &DayOutputDto) {
+        let (year, month, day_of_month) = Self::split_id(day.id);
+        if day.gregorian != format!("{day_of_month:02}/{month:02}/{year}") {
+            self.problem(format!(
+                "day {}: gregorian date {} does not match its id",
+                day.id, day.gregorian
+            ));
+        }
+        for (prayer, time) in day.prayer_times.named() {
+            if minutes_since_midnight(time).is_none() {
+                self.problem(format!(
+                    "day {}: {prayer} time {time:?} is not HH:MM",
+                    day.id
+                ));
+            }
+        }
+    }
+
+    /// Reads `path` both as the DTO it should match, which validates its schema, and as raw json
+    fn load<T: DeserializeOwned>(&mut self, path: PathBuf) -> Option<(T, Value)> {
+        let name = path
+            .strip_prefix(&self.output_dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let value = match File::open(&path) {
+            Ok(file) => match serde_json::from_reader::<_, Value>(file) {
+                Ok(value) => value,
+                Err(e) => {
+                    self.problem(format!("{name}: invalid json: {e}"));
+                    return None;
+                }
+            },
+            Err(e) => {
+                self.problem(format!("{name}: {e}"));
+                return None;
+            }
+        };
+        match serde_json::from_value::<T>(value.clone()) {
+            Ok(dto) => Some((dto, value)),
+            Err(e) => {
+                self.problem(format!("{name}: does not match the schema: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Like [`Verifier::load`], but a missing file is an index that was left out of the run
+    fn load_generated<T: DeserializeOwned>(&mut self, path: PathBuf) -> Option<(T, Value)> {
+        if !path.exists() {
+            return None;
+        }
+        self.load(path)
+    }
+
+    /// Splits a `YYYYMMDD` day id into its components
+    fn split_id(id: u64) -> (u64, u64, u64) {
+        (id / 10000, id / 100 % 100, id % 100)
+    }
+
+    fn problem(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+}
+
+/// Checks every year of the output tree in `output_dir`, or only `year` when given
+pub fn verify(output_dir: &Path, year: Option<u16>) -> Fallible<()> {
+    let mut verifier = Verifier::new(OutputReader::new(output_dir).v1_dir);
+    let years = match year {
+        Some(year) => vec![year],
+        None => verifier.years()?,
+    };
+    if years.is_empty() {
+        anyhow::bail!(
+            "no JSON yearly index found in {} (only the JSON outputs can be verified)",
+            output_dir.display()
+        );
+    }
+    for year in &years {
+        verifier.verify_year(*year);
+    }
+    for problem in &verifier.problems {
+        eprintln!("{problem}");
+    }
+    if !verifier.problems.is_empty() {
+        anyhow::bail!("{} problem(s) found", verifier.problems.len());
+    }
+    println!(
+        "Verified {}",
+        years
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use super::config::ConfigFile;
+use super::params::GenerateParams;
+use super::params::InputFormat;
+use crate::prelude::Fallible;
+use anyhow::Context;
+use notify_debouncer_full::new_debouncer;
+use notify_debouncer_full::notify::RecursiveMode;
+use notify_debouncer_full::DebounceEventResult;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Generates every run of `params`, then regenerates the runs whose input changed every time the
+/// files have settled for `debounce`, until interrupted
+///
+/// A failing run is reported and leaves its output as it was, since outputs are only replaced
+/// once every index has been written.
+pub fn watch(params: &GenerateParams, debounce: Duration) -> Fallible<()> {
+    let config_path = ConfigFile::discover(params.config.as_deref())?
+        .map(|config| std::path::absolute(config.path))
+        .transpose()?;
+    let mut runs = watched_runs(params)?;
+
+    let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(debounce, None, tx)?;
+    // Editors often replace files instead of writing to them, which only their directory sees
+    let mut watched = BTreeSet::new();
+    if let Some(config_dir) = config_path.as_ref().and_then(|path| path.parent()) {
+        debouncer.watch(config_dir, RecursiveMode::NonRecursive)?;
+        watched.insert(config_dir.to_path_buf());
+    }
+    for (_, dir) in &runs {
+        if watched.contains(dir) {
+            continue;
+        }
+        debouncer.watch(dir, RecursiveMode::NonRecursive)?;
+        watched.insert(dir.clone());
+    }
+
+    for (run, _) in &runs {
+        generate(run);
+    }
+    println!("Watching {} for changes", describe_dirs(&watched));
+    for events in rx {
+        let events = match events {
+            Ok(events) => events,
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("error: failed to watch the input: {e}");
+                }
+                continue;
+            }
+        };
+        let outputs = output_paths(&runs);
+        // Reading the input is an event of its own, which must not trigger a new run
+        let changed = events
+            .into_iter()
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| event.event.paths)
+            .filter(|path| !outputs.iter().any(|output| is_written_by(path, output)))
+            .collect::<Vec<_>>();
+        if changed.is_empty() {
+            continue;
+        }
+
+        if config_path
+            .as_ref()
+            .is_some_and(|config_path| changed.contains(config_path))
+        {
+            let new_runs = match watched_runs(params) {
+                Ok(new_runs) => new_runs,
+                Err(e) => {
+                    report_error(
+                        "the project file is invalid, keeping its previous version",
+                        &e,
+                    );
+                    continue;
+                }
+            };
+            for (_, dir) in &new_runs {
+                if watched.contains(dir) {
+                    continue;
+                }
+                match debouncer.watch(dir, RecursiveMode::NonRecursive) {
+                    Ok(()) => {
+                        watched.insert(dir.clone());
+                    }
+                    Err(e) => eprintln!("error: failed to watch {}: {e}", dir.display()),
+                }
+            }
+            // The runs the project file left as they were are only regenerated when their input
+            // changed along with it
+            for (run, dir) in &new_runs {
+                let unchanged = runs.iter().any(|(old_run, _)| old_run == run);
+                if !unchanged || changed.iter().any(|path| path.starts_with(dir)) {
+                    generate(run);
+                }
+            }
+            runs = new_runs;
+            continue;
+        }
+
+        for (run, dir) in &runs {
+            if changed.iter().any(|path| path.starts_with(dir)) {
+                generate(run);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs a single generation, reporting how it went instead of stopping the watch
+fn generate(run: &GenerateParams) {
+    let started = Instant::now();
+    let name = run.describe();
+    match run.generate() {
+        Ok(()) => println!("{name}: generated in {} ms", started.elapsed().as_millis()),
+        Err(e) => report_error(&format!("{name}: failed, keeping the previous output"), &e),
+    }
+}
+
+fn report_error(context: &str, e: &anyhow::Error) {
+    eprintln!("error: {context}");
+    for cause in e.chain() {
+        eprintln!("  {cause}");
+    }
+}
+
+/// The directory the input and side files of `run` are read from
+fn input_dir(run: &GenerateParams) -> Fallible<PathBuf> {
+    let Some(input) = &run.year_dir else {
+        anyhow::bail!("--input is required");
+    };
+    let input = std::path::absolute(input)?;
+    if input.is_dir() {
+        return Ok(input);
+    }
+    Ok(input.parent().map(Path::to_path_buf).unwrap_or(input))
+}
+
+/// Every run of `params` along with the directory its input is read from
+fn watched_runs(params: &GenerateParams) -> Fallible<Vec<(GenerateParams, PathBuf)>> {
+    let mut runs = vec![];
+    for run in params.with_config()? {
+        if matches!(run.input_format, Some(InputFormat::Stdin)) {
+            anyhow::bail!("the standard input cannot be watched");
+        }
+        let dir = input_dir(&run).with_context(|| run.describe())?;
+        runs.push((run, dir));
+    }
+    Ok(runs)
+}
+
+/// The output directories and archives of the runs, whose changes are not changes of the input
+fn output_paths(runs: &[(GenerateParams, PathBuf)]) -> Vec<PathBuf> {
+    let mut outputs = vec![];
+    for (run, _) in runs {
+        let output_dir = run.output_dir_path.as_ref().map(|dir| pathbuf![dir, "v1"]);
+        outputs.extend(
+            [output_dir, run.archive.clone()]
+                .into_iter()
+                .flatten()
+                .flat_map(std::path::absolute),
+        );
+    }
+    outputs
+}
+
+/// Whether `path` is `output`, a file in it, its parent which is created along with it, or one
+/// of the `.<name>.*` staging and temporary siblings it is written through
+fn is_written_by(path: &Path, output: &Path) -> bool {
+    if path.starts_with(output) || output.parent() == Some(path) {
+        return true;
+    }
+    let (Some(name), Some(output_name)) = (path.file_name(), output.file_name()) else {
+        return false;
+    };
+    let prefix = format!(".{}.", output_name.to_string_lossy());
+    path.parent() == output.parent() && name.to_string_lossy().starts_with(&prefix)
+}
+
+fn describe_dirs(dirs: &BTreeSet<PathBuf>) -> String {
+    dirs.iter()
+        .map(|dir| dir.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}